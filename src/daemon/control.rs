@@ -2,9 +2,11 @@ use crate::daemon::Config;
 use crate::daemon::config::{Known, Project};
 use crate::daemon::task_manager::TaskManager;
 use crate::project_manager;
+use crate::project_manager::state::ServerState;
 use axum::extract::{Path, State};
 use axum::response::{IntoResponse, Response};
 use axum::{Extension, Json, http::StatusCode};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fs;
@@ -18,6 +20,46 @@ pub(crate) struct ErrorResponse {
     pub(crate) error: String,
 }
 
+/// 项目的运行状态
+#[derive(Serialize)]
+pub(crate) struct ProjectStatus {
+    /// 服务端是否在运行，已停止、崩溃或准备失败时为 false
+    pub(crate) running: bool,
+    /// 服务端的状态
+    pub(crate) state: ServerState,
+    /// 进入当前状态的时间
+    pub(crate) since: Option<DateTime<Utc>>,
+    /// 最近一次启动进程的时间
+    pub(crate) started_at: Option<DateTime<Utc>>,
+    /// 最近一次就绪的时间
+    pub(crate) ready_at: Option<DateTime<Utc>>,
+}
+
+impl ProjectStatus {
+    /// 从任务管理器中读取项目的状态，没有任务时为未运行，是否运行由服务端的状态决定
+    pub(crate) fn of(task_manager: &TaskManager<String, String>, id: usize) -> Self {
+        match task_manager.get_console(id) {
+            Some(console) => {
+                let status = console.status();
+                ProjectStatus {
+                    running: status.state.is_running(),
+                    state: status.state,
+                    since: Some(status.since),
+                    started_at: status.started_at,
+                    ready_at: status.ready_at,
+                }
+            }
+            None => ProjectStatus {
+                running: false,
+                state: ServerState::Stopped,
+                since: None,
+                started_at: None,
+                ready_at: None,
+            },
+        }
+    }
+}
+
 /// GET 获取状态
 pub async fn status() -> Response {
     debug!("A status request was responded");
@@ -41,7 +83,8 @@ pub async fn list(
     #[derive(Serialize)]
     struct Project {
         id: usize,
-        running: bool,
+        #[serde(flatten)]
+        status: ProjectStatus,
         name: String,
         server_type: String,
         version: String,
@@ -84,7 +127,7 @@ pub async fn list(
             })?;
         list_response.projects.push(Project {
             id: i.id,
-            running: task_manager.exists(i.id),
+            status: ProjectStatus::of(&task_manager, i.id),
             name: config.project.name,
             server_type: format!("{:?}", config.project.server_type),
             version: config.project.version,
@@ -1,10 +1,12 @@
 use crate::daemon::Config as DaemonConfig;
 use crate::daemon::config::Known;
-use crate::daemon::control::ErrorResponse;
+use crate::daemon::control::{ErrorResponse, ProjectStatus};
 use crate::daemon::task_manager::TaskManager;
 use crate::daemon::websocket::WebSocketManager;
+use crate::project_manager::console::Console;
 use crate::project_manager::pre_run;
-use crate::project_manager::run::{backup_thread, server_thread};
+use crate::project_manager::run::{backup_thread, schedule_thread, server_thread};
+use crate::project_manager::state::ServerState;
 use axum::extract::{Multipart, Path as AxumPath, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
@@ -89,16 +91,30 @@ pub async fn start(
             .into_response());
     }
     // 创建任务
-    task_manager.spawn_task(project.id, |rx, tx, stop| async move {
+    let console = Console::new(project_config.project.server_type.clone());
+    console.set_state(ServerState::Preparing);
+    task_manager.spawn_task(project.id, console.clone(), |rx, tx, stop| async move {
         let config = Arc::from(project_config);
         let config_clone = Arc::clone(&config);
         let pre_result = spawn_blocking(move || pre_run(config_clone.as_ref())).await;
         match pre_result {
-            Ok(_) => {
-                spawn(backup_thread(config.clone(), stop.clone()));
-                spawn(server_thread(rx, tx, stop.clone(), config.clone()));
+            Ok(Ok(_)) => {
+                spawn(backup_thread(config.clone(), stop.clone(), console.clone()));
+                spawn(schedule_thread(
+                    config.clone(),
+                    stop.clone(),
+                    console.clone(),
+                ));
+                spawn(server_thread(rx, tx, stop.clone(), config.clone(), console));
+            }
+            Ok(Err(e)) => {
+                error!("Failed to prepare project: {}", e);
+                console.set_state(ServerState::Crashed);
+            }
+            Err(e) => {
+                error!("Failed to prepare project: {}", e);
+                console.set_state(ServerState::Crashed);
             }
-            Err(_) => error!("Failed to prepare project"),
         }
     });
 
@@ -139,6 +155,48 @@ pub async fn stop(
         .into_response())
 }
 
+/// GET 获取服务器的运行状态
+pub async fn status(
+    config: State<Arc<DaemonConfig>>,
+    task_manager: Extension<Arc<TaskManager<String, String>>>,
+    AxumPath(id): AxumPath<usize>,
+) -> Result<Response, Response> {
+    #[derive(Serialize)]
+    struct StatusResponse {
+        success: bool,
+        #[serde(flatten)]
+        status: ProjectStatus,
+    }
+    // 读取已知列表
+    let known = Known::from_file(config.storage.work_dir.join("known.toml")).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                success: false,
+                error: e.to_string(),
+            }),
+        )
+            .into_response()
+    })?;
+    // 查找项目
+    if !known.project.iter().any(|x| x.id == id) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                success: false,
+                error: "The project cannot be found".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    Ok(Json(StatusResponse {
+        success: true,
+        status: ProjectStatus::of(&task_manager, id),
+    })
+    .into_response())
+}
+
 /// Download 请求体
 #[derive(Deserialize)]
 pub struct Download {
@@ -1,7 +1,7 @@
 use crate::daemon::config;
 use crate::daemon::config::{ApiAddr, Known, Token};
 use crate::daemon::control::{add, create, list, remove, status};
-use crate::daemon::project::{connect, download, start, stop, upload};
+use crate::daemon::project::{connect, download, start, status as project_status, stop, upload};
 use crate::daemon::task_manager::TaskManager;
 use crate::daemon::websocket::{WebSocketManager, terminal};
 use anyhow::Error;
@@ -93,6 +93,7 @@ pub fn server(config: config::Config) -> Result<(), Error> {
             .route("/control/remove/{id}", get(remove))
             .route("/project/{id}/start", get(start))
             .route("/project/{id}/stop", get(stop))
+            .route("/project/{id}/status", get(project_status))
             .route("/project/{id}/download", post(download))
             .route("/project/{id}/upload", post(upload))
             .route("/project/{id}/connect", get(connect))
@@ -1,3 +1,4 @@
+use crate::project_manager::console::Console;
 use std::time::Duration;
 use std::{
     collections::HashMap,
@@ -14,6 +15,8 @@ pub struct TaskHandle<In, Out> {
     pub from_task_rx: Arc<tokio::sync::Mutex<mpsc::Receiver<Out>>>,
     pub stop: Arc<Notify>,
     pub handle: JoinHandle<()>,
+    /// 任务运行的服务端的控制台
+    pub console: Arc<Console>,
 }
 
 /// 泛型任务管理器
@@ -29,7 +32,7 @@ impl<In: Send + 'static, Out: Send + 'static> TaskManager<In, Out> {
     }
 
     /// 创建并运行任务
-    pub fn spawn_task<F, Fut>(&self, id: usize, func: F)
+    pub fn spawn_task<F, Fut>(&self, id: usize, console: Arc<Console>, func: F)
     where
         F: FnOnce(mpsc::Receiver<In>, mpsc::Sender<Out>, Arc<Notify>) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
@@ -48,6 +51,7 @@ impl<In: Send + 'static, Out: Send + 'static> TaskManager<In, Out> {
             from_task_rx: Arc::new(tokio::sync::Mutex::new(from_task_rx)),
             stop,
             handle,
+            console,
         };
 
         self.tasks.lock().unwrap().insert(id, handle);
@@ -71,6 +75,15 @@ impl<In: Send + 'static, Out: Send + 'static> TaskManager<In, Out> {
             .map(|t| t.from_task_rx.clone())
     }
 
+    /// 获取任务的服务端控制台
+    pub fn get_console(&self, id: usize) -> Option<Arc<Console>> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|t| t.console.clone())
+    }
+
     /// 停止指定任务并移除
     pub async fn stop_task(&self, id: usize) {
         // 先从 HashMap 里取出 Arc<Task>
@@ -3,7 +3,10 @@ mod project_manager;
 
 use crate::project_manager::run::generate_scripts;
 use crate::project_manager::{
-    CACHE_DIR, create_project, get_info, pre_run, print_info, start_server,
+    BackupRepo, CACHE_DIR, CreateOptions, NewPassword, apply_properties, check_backups,
+    create_backup, create_project, diff_backups, diff_properties, doctor, forget_backups, get_info,
+    list_backups, pre_run, print_info, prune_backups, rekey_backups, restore_backup, start_server,
+    update_plugins, upgrade_server,
 };
 use clap::{Parser, Subcommand};
 use colored::Colorize;
@@ -39,11 +42,18 @@ enum Commands {
     New {
         /// The path of the new directory
         path: PathBuf,
+        #[command(flatten)]
+        options: CreateOptions,
     },
     /// Create a project at the current location
-    Init,
+    Init {
+        #[command(flatten)]
+        options: CreateOptions,
+    },
     /// Install the necessary files to make the project run properly
     Install,
+    /// Check the project and the host for problems
+    Doctor,
     /// Update the plugins
     Update {
         /// Automatically confirm for update
@@ -51,7 +61,27 @@ enum Commands {
         yes: bool,
     },
     /// Upgrade the server core
-    Upgrade,
+    Upgrade {
+        /// The game version to upgrade to, "latest" for the latest version
+        #[arg(long)]
+        to: Option<String>,
+        /// Undo the last upgrade
+        #[arg(short, long, conflicts_with = "to")]
+        rollback: bool,
+        /// Automatically confirm for upgrade
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Manage the server.properties with the configuration
+    Properties {
+        #[command(subcommand)]
+        command: PropertiesCommands,
+    },
+    /// Manage the backup snapshots
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
     /// Run the daemon process
     Daemon {
         /// Specify the location of the configuration file
@@ -66,6 +96,86 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum PropertiesCommands {
+    /// Show the properties that differ from the configuration
+    Diff,
+    /// Write the properties in the configuration to server.properties
+    Apply,
+}
+
+#[derive(Subcommand)]
+enum BackupCommands {
+    /// List the snapshots
+    List {
+        /// Only list the snapshots in this repository
+        #[arg(short, long)]
+        repo: Option<BackupRepo>,
+    },
+    /// Create a snapshot now
+    Create {
+        /// Only back up this repository, defaults to the repositories enabled in the configuration
+        #[arg(short, long)]
+        repo: Option<BackupRepo>,
+    },
+    /// Restore a snapshot into place or into another directory
+    Restore {
+        /// The repository of the snapshot
+        #[arg(value_enum)]
+        repo: BackupRepo,
+        /// The snapshot ID or "latest"
+        snapshot: String,
+        /// Restore into this directory instead of overwriting the current files
+        #[arg(short, long)]
+        target: Option<PathBuf>,
+        /// Automatically confirm for overwriting
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Delete snapshots
+    Forget {
+        /// The repository of the snapshots
+        #[arg(value_enum)]
+        repo: BackupRepo,
+        /// The snapshot IDs
+        #[arg(required = true)]
+        snapshots: Vec<String>,
+        /// Automatically confirm for deletion
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show the files changed between two snapshots
+    Diff {
+        /// The repository of the snapshots
+        #[arg(value_enum)]
+        repo: BackupRepo,
+        /// The older snapshot
+        from: String,
+        /// The newer snapshot, defaults to the latest one
+        to: Option<String>,
+    },
+    /// Apply the retention policy in the configuration now
+    Prune {
+        /// Only prune this repository
+        #[arg(short, long)]
+        repo: Option<BackupRepo>,
+    },
+    /// Change the password of the repositories and their copies
+    Rekey {
+        /// Only change the password of this repository
+        #[arg(short, long)]
+        repo: Option<BackupRepo>,
+        #[command(flatten)]
+        password: NewPassword,
+    },
+    /// Check the integrity of the repositories
+    Check {
+        /// Only check this repository
+        #[arg(short, long)]
+        repo: Option<BackupRepo>,
+    },
+}
+
 fn main() {
     // 启用日志输出
     tracing_subscriber::fmt()
@@ -112,7 +222,7 @@ fn main() {
     }
 
     // new 子命令，根据传入的地址创建目录并初始化项目
-    if let Commands::New { path } = &cli.command {
+    if let Commands::New { path, options } = &cli.command {
         // 创建目录
         fs::create_dir(path).unwrap_or_else(|e| {
             error!("{}", e);
@@ -122,13 +232,13 @@ fn main() {
         std::env::set_current_dir(path)
             .unwrap_or_else(|_| panic!("{}", "The directory cannot be opened!".red()));
         // 初始化项目
-        create_project()
+        create_project(options)
     }
 
     // init 子命令，初始化当前目录
-    if let Commands::Init = &cli.command {
+    if let Commands::Init { options } = &cli.command {
         // 初始化项目
-        create_project()
+        create_project(options)
     }
 
     // install 子命令，执行运行前准备工作
@@ -140,6 +250,76 @@ fn main() {
         };
     }
 
+    // doctor 子命令，检查项目和主机环境，存在问题时以非零状态退出
+    if let Commands::Doctor = &cli.command
+        && !doctor()
+    {
+        std::process::exit(1);
+    }
+
+    // update 子命令，按配置更新插件
+    if let Commands::Update { yes } = &cli.command {
+        match get_info() {
+            Ok(v) => update_plugins(&v, *yes).expect("The program exited with errors!"),
+            Err(e) => error!("The configuration cannot be opened: {:?}", e),
+        };
+    }
+
+    // upgrade 子命令，升级服务端核心
+    if let Commands::Upgrade { to, rollback, yes } = &cli.command {
+        if *rollback {
+            project_manager::rollback().expect("The program exited with errors!");
+        } else {
+            match get_info() {
+                Ok(v) => {
+                    upgrade_server(v, to.clone(), *yes).expect("The program exited with errors!")
+                }
+                Err(e) => error!("The configuration cannot be opened: {:?}", e),
+            };
+        }
+    }
+
+    // properties 子命令，管理 server.properties
+    if let Commands::Properties { command } = &cli.command {
+        match get_info() {
+            Ok(v) => match command {
+                PropertiesCommands::Diff => diff_properties(&v),
+                PropertiesCommands::Apply => apply_properties(&v),
+            }
+            .expect("The program exited with errors!"),
+            Err(e) => error!("The configuration cannot be opened: {:?}", e),
+        };
+    }
+
+    // backup 子命令，管理备份快照
+    if let Commands::Backup { command } = &cli.command {
+        match get_info() {
+            Ok(v) => match command {
+                BackupCommands::List { repo } => list_backups(&v, *repo),
+                BackupCommands::Create { repo } => create_backup(&v, *repo),
+                BackupCommands::Restore {
+                    repo,
+                    snapshot,
+                    target,
+                    yes,
+                } => restore_backup(&v, *repo, snapshot, target.as_deref(), *yes),
+                BackupCommands::Forget {
+                    repo,
+                    snapshots,
+                    yes,
+                } => forget_backups(&v, *repo, snapshots, *yes),
+                BackupCommands::Diff { repo, from, to } => {
+                    diff_backups(&v, *repo, from, to.as_deref())
+                }
+                BackupCommands::Prune { repo } => prune_backups(&v, *repo),
+                BackupCommands::Rekey { repo, password } => rekey_backups(&v, *repo, password),
+                BackupCommands::Check { repo } => check_backups(&v, *repo),
+            }
+            .expect("The program exited with errors!"),
+            Err(e) => error!("The configuration cannot be opened: {:?}", e),
+        };
+    }
+
     // daemon 子命令
     if let Commands::Daemon {
         config,
@@ -169,7 +349,7 @@ fn main() {
 
             // 安装 PacMine
             if !work_dir.join("bin").is_dir() {
-                fs::create_dir_all(&work_dir.join("bin"))
+                fs::create_dir_all(work_dir.join("bin"))
                     .expect("Could not create the work directory!");
             }
             fs::copy(
@@ -0,0 +1,396 @@
+use crate::project_manager::Config;
+use crate::project_manager::config::{BackupPassword, BackupRepository};
+use crate::project_manager::console::Console;
+use crate::project_manager::create::get_input;
+use crate::project_manager::run::{init_repository, run_backup};
+use crate::project_manager::tools::backup::{
+    SnapshotChange, backup_apply_retention, backup_check_repo, backup_diff_snaps,
+    backup_forget_snaps, backup_get_snap, backup_list_snaps, backup_open_repo, backup_rekey_repo,
+    backup_restore_snap,
+};
+use anyhow::Error;
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use tokio::runtime::Runtime;
+use tracing::{error, info, warn};
+
+/// 备份仓库
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BackupRepo {
+    /// 世界存档
+    World,
+    /// 世界存档以外的文件
+    Other,
+}
+
+impl BackupRepo {
+    /// 仓库名称
+    fn name(&self) -> &'static str {
+        match self {
+            BackupRepo::World => "world",
+            BackupRepo::Other => "other",
+        }
+    }
+
+    /// 获取配置文件中的仓库
+    fn repository(&self, config: &Config) -> BackupRepository {
+        config.backup.repository(self.name())
+    }
+
+    /// 获取需要操作的仓库，缺省时为所有已配置或已创建的仓库
+    fn select(config: &Config, repo: Option<BackupRepo>) -> Vec<BackupRepo> {
+        match repo {
+            Some(repo) => vec![repo],
+            None => [BackupRepo::World, BackupRepo::Other]
+                .into_iter()
+                .filter(|x| {
+                    x.repository(config)
+                        .backend
+                        .local_path()
+                        .is_none_or(|path| path.exists())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 新的仓库密码，缺省时在终端中输入
+#[derive(Args, Debug, Default, Clone)]
+#[group(multiple = false)]
+pub struct NewPassword {
+    /// Read the new password from this environment variable
+    #[arg(long = "password-env", value_name = "ENV")]
+    pub env: Option<String>,
+    /// Read the new password from the first line of this file
+    #[arg(long = "password-file", value_name = "FILE")]
+    pub file: Option<PathBuf>,
+    /// Read the new password from the output of this command
+    #[arg(long = "password-command", value_name = "COMMAND")]
+    pub command: Option<String>,
+}
+
+/// 列出快照
+pub fn list_backups(config: &Config, repo: Option<BackupRepo>) -> Result<(), Error> {
+    let repos = BackupRepo::select(config, repo);
+    if repos.is_empty() {
+        info!("There is no backup yet");
+    }
+    for repo in repos {
+        println!("{}", format!("[{}]", repo.name()).bold());
+        let snaps = backup_list_snaps(&repo.repository(config).backend)?;
+        if snaps.is_empty() {
+            println!("  No snapshots");
+            continue;
+        }
+        println!(
+            "  {:<8}  {:<19}  {:<12}  {:>10}",
+            "ID", "Time", "Tags", "Size"
+        );
+        for snap in &snaps {
+            let size = snap
+                .summary
+                .as_ref()
+                .map(|x| format!("{:.1} MB", x.total_bytes_processed as f64 / 1024.0 / 1024.0))
+                .unwrap_or_default();
+            println!(
+                "  {:<8}  {:<19}  {:<12}  {:>10}",
+                snap.id.to_string()[..8].yellow(),
+                snap.time.format("%Y-%m-%d %H:%M:%S"),
+                snap.tags.to_string(),
+                size
+            );
+        }
+        println!("  {} snapshots", snaps.len());
+    }
+    Ok(())
+}
+
+/// 立即创建快照，缺省时备份配置文件中启用的仓库
+pub fn create_backup(config: &Config, repo: Option<BackupRepo>) -> Result<(), Error> {
+    let (world, other) = match repo {
+        Some(BackupRepo::World) => (Some(config.backup.repository("world")), None),
+        Some(BackupRepo::Other) => (None, Some(config.backup.repository("other"))),
+        None => config.backup.targets(),
+    };
+    if world.is_none() && other.is_none() {
+        return Err(Error::msg(
+            "No repository is enabled, specify one with `--repo`",
+        ));
+    }
+    for repo in world.iter().chain(other.iter()) {
+        init_repository(repo)?;
+    }
+    Runtime::new()?.block_on(run_backup(
+        "Manual",
+        world,
+        other,
+        config.backup.retention.clone(),
+        Console::new(config.project.server_type.clone()),
+    ))?;
+    info!("{}", "The backup has been created".green());
+    Ok(())
+}
+
+/// 恢复快照，`target` 缺省时覆盖当前的文件
+pub fn restore_backup(
+    config: &Config,
+    repo: BackupRepo,
+    snapshot: &str,
+    target: Option<&Path>,
+    yes: bool,
+) -> Result<(), Error> {
+    let repository = repo.repository(config);
+    let snap = backup_get_snap(&repository.backend, snapshot)?;
+    let id = snap.id.to_string();
+    println!(
+        "Restore the {} snapshot {} created at {} [{}]",
+        repo.name(),
+        id[..8].yellow(),
+        snap.time.format("%Y-%m-%d %H:%M:%S"),
+        snap.tags
+    );
+    if target.is_none() && !yes {
+        println!(
+            "The current files will be overwritten, make sure the server is stopped. Do you want to continue? [y/N]"
+        );
+        if !matches!(get_input().trim().to_lowercase().as_str(), "y" | "yes") {
+            warn!("The restore has been cancelled");
+            return Ok(());
+        }
+    }
+    let world_dir = config.project.server_type.world_dir();
+    if target.is_none() && (repo == BackupRepo::Other || Path::new(world_dir).exists()) {
+        // 覆盖前保存当前的文件，恢复出错时可以找回
+        info!("Back up the current files before restoring");
+        let (world, other) = match repo {
+            BackupRepo::World => (Some(repository.clone()), None),
+            BackupRepo::Other => (None, Some(repository.clone())),
+        };
+        Runtime::new()?.block_on(run_backup(
+            "Restore",
+            world,
+            other,
+            None,
+            Console::new(config.project.server_type.clone()),
+        ))?;
+    }
+
+    // 快照中记录的是备份时的绝对路径，按文件名恢复到项目或指定的目录中，
+    // BDS 运行时备份的快照记录的是存档的相对路径 `worlds/<存档名称>`，恢复到相同的位置，
+    // 避免删除 `worlds` 中的其他存档
+    let base = target.unwrap_or(Path::new("."));
+    for path in snap.paths.iter() {
+        let path = Path::new(path);
+        let destination = if path.is_relative() {
+            base.join(path)
+        } else {
+            base.join(path.file_name().ok_or(Error::msg(format!(
+                "The path {} is invalid",
+                path.display()
+            )))?)
+        };
+        info!("Restore {}", destination.display());
+        backup_restore_snap(
+            &repository.backend,
+            &format!("{}:{}", id, path.display()),
+            &destination.to_string_lossy(),
+            target.is_none(),
+        )?;
+    }
+    info!("{}", "The snapshot has been restored".green());
+    Ok(())
+}
+
+/// 删除快照，不会立即释放空间
+pub fn forget_backups(
+    config: &Config,
+    repo: BackupRepo,
+    ids: &[String],
+    yes: bool,
+) -> Result<(), Error> {
+    if !yes {
+        println!(
+            "{} snapshots in {} will be deleted. Do you want to continue? [y/N]",
+            ids.len(),
+            repo.name()
+        );
+        if !matches!(get_input().trim().to_lowercase().as_str(), "y" | "yes") {
+            warn!("The operation has been cancelled");
+            return Ok(());
+        }
+    }
+    for snap in backup_forget_snaps(&repo.repository(config).backend, ids)? {
+        println!(
+            "  {} {} {}",
+            "-".red(),
+            snap.id.to_string()[..8].yellow(),
+            snap.time.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    Ok(())
+}
+
+/// 对比两个快照，`to` 缺省时为最新的快照
+pub fn diff_backups(
+    config: &Config,
+    repo: BackupRepo,
+    from: &str,
+    to: Option<&str>,
+) -> Result<(), Error> {
+    let changes = backup_diff_snaps(
+        &repo.repository(config).backend,
+        from,
+        to.unwrap_or("latest"),
+    )?;
+    if changes.is_empty() {
+        info!("The snapshots are identical");
+        return Ok(());
+    }
+    for change in &changes {
+        match change {
+            SnapshotChange::Added(path) => println!("  {} {}", "+".green(), path.display()),
+            SnapshotChange::Removed(path) => println!("  {} {}", "-".red(), path.display()),
+            SnapshotChange::Modified(path) => println!("  {} {}", "~".yellow(), path.display()),
+        }
+    }
+    println!("{} changes", changes.len());
+    Ok(())
+}
+
+/// 立即按保留策略清理仓库
+pub fn prune_backups(config: &Config, repo: Option<BackupRepo>) -> Result<(), Error> {
+    let Some(retention) = &config.backup.retention else {
+        return Err(Error::msg(
+            "There is no [backup.retention] section in the configuration",
+        ));
+    };
+    for repo in BackupRepo::select(config, repo) {
+        let forgotten = backup_apply_retention(&repo.repository(config).backend, retention)?;
+        info!("Deleted {} snapshots from {}", forgotten, repo.name());
+    }
+    Ok(())
+}
+
+/// 更换仓库及其副本仓库的密码
+pub fn rekey_backups(
+    config: &Config,
+    repo: Option<BackupRepo>,
+    new: &NewPassword,
+) -> Result<(), Error> {
+    let password = if new.env.is_none() && new.file.is_none() && new.command.is_none() {
+        let password = read_password("Enter the new password:")?;
+        if read_password("Enter the new password again:")? != password {
+            return Err(Error::msg("The passwords do not match"));
+        }
+        BackupPassword {
+            value: Some(password),
+            ..Default::default()
+        }
+    } else {
+        BackupPassword {
+            value: None,
+            env: new.env.clone(),
+            file: new.file.clone(),
+            command: new.command.clone(),
+        }
+    };
+    let repos = BackupRepo::select(config, repo);
+    if repos.is_empty() {
+        info!("There is no backup yet");
+        return Ok(());
+    }
+    let backends: Vec<_> = repos
+        .iter()
+        .map(|repo| repo.repository(config))
+        .flat_map(|repository| std::iter::once(repository.backend).chain(repository.copy))
+        .collect();
+    // 先确认所有仓库都能用当前密码打开，避免只有部分仓库更换了密码
+    for backend in &backends {
+        backup_open_repo(backend).map_err(|e| {
+            Error::msg(format!(
+                "Cannot open {} with the current password, no password was changed: {}",
+                backend.repository, e
+            ))
+        })?;
+    }
+    let mut changed = vec![];
+    for backend in &backends {
+        if let Err(e) = backup_rekey_repo(backend, &password) {
+            if !changed.is_empty() {
+                error!(
+                    "These repositories already use the new password: {}",
+                    changed.join(", ")
+                );
+            }
+            return Err(Error::msg(format!(
+                "Failed to change the password of {}: {}",
+                backend.repository, e
+            )));
+        }
+        info!("Changed the password of {}", backend.repository);
+        changed.push(backend.repository.clone());
+    }
+    warn!(
+        "Update [backup.password] in the configuration, otherwise the repositories cannot be opened"
+    );
+    Ok(())
+}
+
+/// 在终端中输入密码，输入的内容不回显
+#[cfg(target_family = "unix")]
+fn read_password(prompt: &str) -> Result<String, Error> {
+    use nix::sys::termios::{LocalFlags, SetArg, tcgetattr, tcsetattr};
+    use std::io::Write;
+    let stdin = std::io::stdin();
+    let Ok(original) = tcgetattr(&stdin) else {
+        return Err(Error::msg(
+            "Not a terminal, use --password-env, --password-file or --password-command",
+        ));
+    };
+    let mut silent = original.clone();
+    silent.local_flags.remove(LocalFlags::ECHO);
+    silent.local_flags.insert(LocalFlags::ECHONL);
+    print!("{} ", prompt);
+    std::io::stdout().flush()?;
+    tcsetattr(&stdin, SetArg::TCSANOW, &silent)?;
+    let mut input = String::new();
+    let result = stdin.read_line(&mut input);
+    // 无论读取是否成功都要恢复回显
+    tcsetattr(&stdin, SetArg::TCSANOW, &original)?;
+    result?;
+    Ok(input.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// 其他平台无法关闭回显，要求从其他来源读取密码
+#[cfg(not(target_family = "unix"))]
+fn read_password(_prompt: &str) -> Result<String, Error> {
+    Err(Error::msg(
+        "Use --password-env, --password-file or --password-command to set the new password",
+    ))
+}
+
+/// 检查仓库
+pub fn check_backups(config: &Config, repo: Option<BackupRepo>) -> Result<(), Error> {
+    let repos = BackupRepo::select(config, repo);
+    if repos.is_empty() {
+        info!("There is no backup yet");
+    }
+    let mut failed = vec![];
+    for repo in repos {
+        match backup_check_repo(&repo.repository(config).backend) {
+            Ok(_) => info!("The {} repository is healthy", repo.name()),
+            Err(e) => {
+                warn!("The {} repository is damaged: {}", repo.name(), e);
+                failed.push(repo.name());
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(Error::msg(format!(
+            "Damaged repositories: {}",
+            failed.join(", ")
+        )));
+    }
+    Ok(())
+}
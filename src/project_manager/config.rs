@@ -1,13 +1,16 @@
-use crate::project_manager::RUNTIME_DIR;
-use crate::project_manager::tools::check_java;
+use crate::project_manager::BACKUP_DIR;
+use crate::project_manager::lock::Lock;
+use crate::project_manager::tools::{LaunchSpec, check_java, java_binary, runtime_path};
 pub(crate) use crate::project_manager::tools::{ServerType, VersionType};
 use anyhow::Error;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use std::cmp::PartialEq;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::debug;
 
 /// 实例配置文件
@@ -15,12 +18,24 @@ use tracing::debug;
 pub struct Config {
     /// 实例基本信息
     pub(crate) project: Project,
+    /// 自定义服务端的下载模板，仅 `server_type` 为 `Other` 时生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) provider: Option<ProviderTemplate>,
     /// 运行环境配置
     pub(crate) runtime: Runtime,
     /// 备份配置
     pub(crate) backup: Backup,
     /// 插件管理配置
     pub(crate) plugin_manage: PluginManage,
+    /// 写入 `server.properties` 的配置项，未列出的配置项保持不变
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) properties: BTreeMap<String, toml::Value>,
+    /// 受管理的插件列表
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) plugin: Vec<Plugin>,
+    /// 服务端运行期间的计划任务
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) schedule: Vec<Schedule>,
 }
 
 /// 实例的基本信息
@@ -34,17 +49,234 @@ pub struct Project {
     pub(crate) version: String,
     /// 服务端版本类型
     pub(crate) version_type: VersionType,
+    /// 模组加载器版本，仅 Fabric、Quilt、Forge 和 NeoForge 生效，缺省时使用最新的稳定版本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) loader_version: Option<String>,
+    /// 锁定的构建号，仅 Paper 类服务端生效，缺省时使用 `channel` 中的最新构建
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) build: Option<usize>,
+    /// 可接受的最低构建通道，仅 PaperMC 的服务端生效，缺省时为 `stable`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) channel: Option<BuildChannel>,
     /// 服务端可执行文件路径
     pub(crate) execute: PathBuf,
     /// 服务器创建日期
     pub(crate) birthday: chrono::DateTime<chrono::Utc>,
 }
 
+/// 自定义服务端的下载模板
+///
+/// 链接中的 `{version}`、`{build}` 和 `{loader}` 会被替换为游戏版本、构建号和 `loader_version`，
+/// 名称以 `_pointer` 结尾的字段为 JSON Pointer，为空时指向整个响应
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProviderTemplate {
+    /// 服务端名称，仅用于显示
+    pub(crate) name: String,
+    /// 版本列表 API，缺省时不检查版本，也无法获取最新版本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) versions_url: Option<String>,
+    /// 版本列表在响应中的位置，需要指向字符串数组
+    #[serde(default)]
+    pub(crate) versions_pointer: String,
+    /// 构建列表 API，缺省时只使用 `build` 锁定的构建号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) builds_url: Option<String>,
+    /// 构建列表在响应中的位置，需要指向数组
+    #[serde(default)]
+    pub(crate) builds_pointer: String,
+    /// 构建号在单个构建中的位置，构建列表为构建号数组时为空
+    #[serde(default)]
+    pub(crate) build_pointer: String,
+    /// 校验值在单个构建中的位置，缺省时不校验
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) checksum_pointer: Option<String>,
+    /// 校验值的类型
+    #[serde(default)]
+    pub(crate) checksum_type: ChecksumType,
+    /// 下载链接
+    pub(crate) download_url: String,
+    /// 启动方式
+    #[serde(default)]
+    pub(crate) launch: LaunchSpec,
+}
+
+/// 校验值类型
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumType {
+    Md5,
+    Sha1,
+    #[default]
+    Sha256,
+}
+
 /// 运行环境管理
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Runtime {
     /// Java 运行时
     pub(crate) java: Java,
+    /// 服务端自行退出后的重启策略，缺省时不重启
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) restart: Option<Restart>,
+    /// 停止服务端的方式，缺省时按服务端类型选择
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) shutdown: Option<Shutdown>,
+}
+
+/// 停止服务端的方式，依次发送停止命令、SIGTERM 和 SIGKILL，直到服务端退出
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Shutdown {
+    /// 停止服务端的命令，缺省时 Java 版和 BDS 为 `stop`，代理服务端为 `end`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) command: Option<String>,
+    /// 发送停止命令后等待退出的时间，单位秒，缺省时代理服务端为 10，BDS 为 30，其他为 60
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) timeout: Option<u64>,
+    /// 发送 SIGTERM 后等待退出的时间，单位秒，超时后强制结束，仅在 Unix 中生效
+    #[serde(default = "default_term_timeout", alias = "term-timeout")]
+    pub(crate) term_timeout: u64,
+    /// 停止前向玩家广播倒计时的时间，单位秒，`0` 为直接停止
+    #[serde(default)]
+    pub(crate) countdown: u64,
+    /// 倒计时广播的消息，`{}` 替换为剩余的秒数
+    #[serde(default = "default_countdown_message")]
+    pub(crate) message: String,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown {
+            command: None,
+            timeout: None,
+            term_timeout: default_term_timeout(),
+            countdown: 0,
+            message: default_countdown_message(),
+        }
+    }
+}
+
+impl Shutdown {
+    /// 停止服务端的命令
+    pub fn command(&self, server_type: &ServerType) -> &str {
+        self.command
+            .as_deref()
+            .unwrap_or(server_type.stop_command())
+    }
+
+    /// 发送停止命令后等待退出的时间
+    pub fn timeout(&self, server_type: &ServerType) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(server_type.stop_timeout()))
+    }
+
+    /// 倒计时中广播的时间点，为剩余的秒数，从大到小排列
+    pub fn countdown_marks(&self) -> Vec<u64> {
+        let mut marks = vec![];
+        if self.countdown > 0 {
+            marks.push(self.countdown);
+        }
+        marks.extend(
+            [60, 30, 10, 5, 4, 3, 2, 1]
+                .into_iter()
+                .filter(|x| *x < self.countdown),
+        );
+        marks
+    }
+}
+
+/// SIGTERM 后等待时间的默认值
+fn default_term_timeout() -> u64 {
+    10
+}
+
+/// 倒计时广播消息的默认值
+fn default_countdown_message() -> String {
+    "The server will stop in {} seconds".to_string()
+}
+
+/// 服务端自行退出后的重启策略
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Restart {
+    /// 重启的条件
+    #[serde(default)]
+    pub(crate) policy: RestartPolicy,
+    /// 连续崩溃时最多重启的次数，超出后视为崩溃循环，不再重启
+    #[serde(default = "default_max_retries", alias = "max-retries")]
+    pub(crate) max_retries: u32,
+    /// 第一次重启前等待的时间，单位秒，之后每次翻倍
+    #[serde(default = "default_backoff")]
+    pub(crate) backoff: u64,
+    /// 重启前等待时间的上限，单位秒
+    #[serde(default = "default_max_backoff", alias = "max-backoff")]
+    pub(crate) max_backoff: u64,
+    /// 崩溃循环的检测窗口，单位秒，服务端运行超过该时间后重新计算重启次数
+    #[serde(default = "default_window")]
+    pub(crate) window: u64,
+}
+
+/// 重启的条件
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// 不重启
+    #[default]
+    Never,
+    /// 仅在退出状态不为成功时重启
+    #[serde(alias = "on_failure")]
+    OnFailure,
+    /// 总是重启，除非由 PacMine 停止
+    Always,
+}
+
+impl Default for Restart {
+    fn default() -> Self {
+        Restart {
+            policy: RestartPolicy::default(),
+            max_retries: default_max_retries(),
+            backoff: default_backoff(),
+            max_backoff: default_max_backoff(),
+            window: default_window(),
+        }
+    }
+}
+
+impl Restart {
+    /// 服务端以 `success` 的状态退出后是否需要重启
+    pub fn should_restart(&self, success: bool) -> bool {
+        match self.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !success,
+            RestartPolicy::Always => true,
+        }
+    }
+
+    /// 第 `attempt` 次重启前等待的时间，从 0 开始计数
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .backoff
+            .saturating_mul(2u64.saturating_pow(attempt))
+            .min(self.max_backoff);
+        Duration::from_secs(delay)
+    }
+}
+
+/// 最多重启次数的默认值
+fn default_max_retries() -> u32 {
+    5
+}
+
+/// 第一次重启前等待时间的默认值
+fn default_backoff() -> u64 {
+    5
+}
+
+/// 等待时间上限的默认值
+fn default_max_backoff() -> u64 {
+    300
+}
+
+/// 崩溃循环检测窗口的默认值
+fn default_window() -> u64 {
+    600
 }
 
 /// Java 环境配置
@@ -105,6 +337,135 @@ pub struct Backup {
     /// 根据事件备份
     #[serde(default)]
     pub(crate) event: Option<Event>,
+    /// 快照保留策略，缺省时保留所有快照
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) retention: Option<Retention>,
+    /// 备份仓库的位置，缺省时使用项目中的仓库
+    #[serde(default, skip_serializing_if = "Repositories::is_empty")]
+    pub(crate) repository: Repositories,
+    /// 备份仓库的密码，缺省时使用空密码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) password: Option<BackupPassword>,
+}
+
+impl Backup {
+    /// 获取备份仓库，`name` 为 `world` 或 `other`，未配置时使用 `{BACKUP_DIR}/<name>`，
+    /// 没有单独设置密码的仓库使用 `[backup.password]`
+    pub fn repository(&self, name: &str) -> BackupRepository {
+        let configured = match name {
+            "world" => self.repository.world.as_ref(),
+            _ => self.repository.other.as_ref(),
+        };
+        let mut repo = configured.cloned().unwrap_or(BackupRepository {
+            backend: BackupBackend {
+                repository: format!("{}/{}", BACKUP_DIR, name),
+                ..Default::default()
+            },
+            copy: vec![],
+        });
+        for backend in [&mut repo.backend].into_iter().chain(&mut repo.copy) {
+            if backend.password.is_none() {
+                backend.password = self.password.clone();
+            }
+        }
+        repo
+    }
+
+    /// 获取启用的仓库
+    pub fn targets(&self) -> (Option<BackupRepository>, Option<BackupRepository>) {
+        (
+            self.world.then(|| self.repository("world")),
+            self.other.then(|| self.repository("other")),
+        )
+    }
+}
+
+/// 自定义的备份仓库
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Repositories {
+    /// 地图仓库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) world: Option<BackupRepository>,
+    /// 地图以外内容的仓库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) other: Option<BackupRepository>,
+}
+
+impl Repositories {
+    fn is_empty(&self) -> bool {
+        self.world.is_none() && self.other.is_none()
+    }
+}
+
+/// 备份仓库及其副本
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct BackupRepository {
+    /// 仓库的存储位置
+    #[serde(flatten)]
+    pub(crate) backend: BackupBackend,
+    /// 每次备份后同步快照的副本仓库
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) copy: Vec<BackupBackend>,
+}
+
+/// 仓库的存储位置，对应 rustic 的后端选项
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct BackupBackend {
+    /// 本地路径，或 `opendal:<服务>`、`rclone:<远程>:<路径>` 等 rustic 支持的后端
+    pub(crate) repository: String,
+    /// 存放元数据的热存储仓库，用于访问较慢的冷存储
+    #[serde(default, alias = "repo-hot", skip_serializing_if = "Option::is_none")]
+    pub(crate) repo_hot: Option<String>,
+    /// 后端选项，如 S3 的 `bucket`、`endpoint` 或 SFTP 的 `user`、`key`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) options: BTreeMap<String, String>,
+    /// 仅用于热存储仓库的后端选项
+    #[serde(
+        default,
+        alias = "options-hot",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub(crate) options_hot: BTreeMap<String, String>,
+    /// 仅用于冷存储仓库的后端选项
+    #[serde(
+        default,
+        alias = "options-cold",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub(crate) options_cold: BTreeMap<String, String>,
+    /// 仓库的密码，缺省时使用 `[backup.password]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) password: Option<BackupPassword>,
+}
+
+/// 备份仓库的密码来源，使用 `value`、`env`、`file`、`command` 中第一个设置的值
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct BackupPassword {
+    /// 密码明文，不建议在共享的配置文件中使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) value: Option<String>,
+    /// 保存密码的环境变量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) env: Option<String>,
+    /// 保存密码的文件，使用文件的第一行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) file: Option<PathBuf>,
+    /// 输出密码的命令，如 `pass show pacmine`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) command: Option<String>,
+}
+
+impl BackupBackend {
+    /// 本地仓库的路径，其他后端返回 None
+    pub fn local_path(&self) -> Option<&Path> {
+        match self.repository.strip_prefix("local:") {
+            Some(path) => Some(Path::new(path)),
+            None if !self.repository.contains(':') || Path::new(&self.repository).is_absolute() => {
+                Some(Path::new(&self.repository))
+            }
+            None => None,
+        }
+    }
 }
 
 /// 根据时间备份的选项
@@ -128,6 +489,82 @@ pub struct Event {
     pub(crate) update: bool,
 }
 
+/// 快照保留策略，每次备份后对每个仓库分别执行
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Retention {
+    /// 保留最近的快照数量
+    #[serde(default, alias = "keep-last", skip_serializing_if = "Option::is_none")]
+    pub(crate) keep_last: Option<u32>,
+    /// 保留最近若干小时中每小时的最后一个快照
+    #[serde(
+        default,
+        alias = "keep-hourly",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) keep_hourly: Option<u32>,
+    /// 保留最近若干天中每天的最后一个快照
+    #[serde(default, alias = "keep-daily", skip_serializing_if = "Option::is_none")]
+    pub(crate) keep_daily: Option<u32>,
+    /// 保留最近若干周中每周的最后一个快照
+    #[serde(
+        default,
+        alias = "keep-weekly",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) keep_weekly: Option<u32>,
+    /// 保留最近若干月中每月的最后一个快照
+    #[serde(
+        default,
+        alias = "keep-monthly",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) keep_monthly: Option<u32>,
+    /// 始终保留带有其中任一标签的快照，如 `Stop`
+    #[serde(default, alias = "keep-tags", skip_serializing_if = "Vec::is_empty")]
+    pub(crate) keep_tags: Vec<String>,
+    /// 仓库大小上限，单位 MB，超出时从最早的快照开始删除，缺省或 0 不限
+    #[serde(default, alias = "max-size", skip_serializing_if = "Option::is_none")]
+    pub(crate) max_size: Option<u64>,
+}
+
+impl Retention {
+    /// 是否设置了按数量保留的规则
+    pub fn has_keep_rules(&self) -> bool {
+        self.keep_last.is_some()
+            || self.keep_hourly.is_some()
+            || self.keep_daily.is_some()
+            || self.keep_weekly.is_some()
+            || self.keep_monthly.is_some()
+    }
+}
+
+/// 计划任务
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Schedule {
+    /// 执行的时间点，格式为 `Cron` 表达式，与 `[backup.time]` 的 `cron` 相同
+    pub(crate) cron: String,
+    /// 执行的操作
+    #[serde(flatten)]
+    pub(crate) action: ScheduleAction,
+}
+
+/// 计划任务的操作，由 `action` 字段选择
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ScheduleAction {
+    /// 依次向服务端发送命令
+    Command { commands: Vec<String> },
+    /// 重启服务端，`countdown` 缺省时使用 `[runtime.shutdown]` 的倒计时
+    Restart {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        countdown: Option<u64>,
+    },
+    /// 备份启用的仓库
+    Backup,
+    /// 在项目目录中运行命令，Unix 中使用 `sh -c`，Windows 中使用 `cmd /C`
+    Hook { command: String },
+}
+
 /// 插件管理功能
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PluginManage {
@@ -135,6 +572,60 @@ pub struct PluginManage {
     pub(crate) manage: bool,
 }
 
+/// 受管理的插件
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Plugin {
+    /// 插件名称，同时作为 Modrinth/Hangar 的项目 ID 或 Slug
+    pub(crate) name: String,
+    /// 插件来源
+    pub(crate) source: PluginSource,
+    /// 版本约束，`latest` 或 `*` 为最新版本，支持形如 `5.1.*` 的通配
+    #[serde(default = "default_plugin_version")]
+    pub(crate) version: String,
+    /// 直接下载的 URL，仅当 `source` 为 `url` 时生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) url: Option<String>,
+    /// 校验值，形如 `sha256:<hex>` 或 `sha1:<hex>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) checksum: Option<String>,
+}
+
+/// PaperMC 的构建通道，按稳定程度从低到高排列
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildChannel {
+    /// 实验性的构建
+    #[serde(alias = "ALPHA", alias = "experimental")]
+    Alpha,
+    /// 测试中的构建
+    #[serde(alias = "BETA")]
+    Beta,
+    /// 稳定的构建
+    #[default]
+    #[serde(alias = "STABLE", alias = "default")]
+    Stable,
+    /// 推荐的构建
+    #[serde(alias = "RECOMMENDED")]
+    Recommended,
+}
+
+/// 插件来源
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginSource {
+    /// https://modrinth.com
+    Modrinth,
+    /// https://hangar.papermc.io
+    Hangar,
+    /// 直接下载链接
+    Url,
+}
+
+/// 插件版本约束的默认值
+fn default_plugin_version() -> String {
+    "latest".to_string()
+}
+
 /// 为 Config 定义方法
 impl Config {
     /// 从文件读取 TOML
@@ -162,9 +653,13 @@ impl Default for Config {
                 server_type: ServerType::Vanilla,
                 execute: PathBuf::from("server.jar"),
                 version: "latest".to_string(),
+                loader_version: None,
+                build: None,
+                channel: None,
                 birthday: chrono::Utc::now(),
                 version_type: VersionType::Release,
             },
+            provider: None,
             runtime: Runtime {
                 java: Java {
                     mode: JavaMode::Auto,
@@ -175,6 +670,8 @@ impl Default for Config {
                     xms: 0,
                     xmx: 0,
                 },
+                restart: None,
+                shutdown: None,
             },
             backup: Backup {
                 enable: true,
@@ -189,8 +686,14 @@ impl Default for Config {
                     stop: true,
                     update: true,
                 }),
+                retention: None,
+                repository: Repositories::default(),
+                password: None,
             },
             plugin_manage: PluginManage { manage: true },
+            properties: BTreeMap::new(),
+            plugin: vec![],
+            schedule: vec![],
         }
     }
 }
@@ -224,6 +727,18 @@ impl Display for Config {
             key("Version Type:"),
             self.project.version_type
         )?;
+        if let Some(loader_version) = &self.project.loader_version {
+            writeln!(f, "  {} {}", key("Loader:"), loader_version)?;
+        }
+        if let Some(build) = self.project.build {
+            writeln!(f, "  {} {}", key("Build:"), build)?;
+        }
+        if let Some(channel) = &self.project.channel {
+            writeln!(f, "  {} {:?}", key("Channel:"), channel)?;
+        }
+        if let Some(provider) = &self.provider {
+            writeln!(f, "  {} {}", key("Provider:"), provider.name)?;
+        }
         writeln!(f, "  {} {:?}", key("Executable:"), self.project.execute)?;
         writeln!(
             f,
@@ -246,6 +761,34 @@ impl Display for Config {
         )?;
         writeln!(f, "  {} {} MB", key("Xms:"), self.runtime.java.xms)?;
         writeln!(f, "  {} {} MB", key("Xmx:"), self.runtime.java.xmx)?;
+        if let Some(ref restart) = self.runtime.restart {
+            writeln!(f, "{}", title("Runtime → Restart"))?;
+            writeln!(f, "  {} {:?}", key("Policy:"), restart.policy)?;
+            writeln!(f, "  {} {}", key("Max Retries:"), restart.max_retries)?;
+            writeln!(
+                f,
+                "  {} {}s - {}s",
+                key("Backoff:"),
+                restart.backoff,
+                restart.max_backoff
+            )?;
+            writeln!(f, "  {} {}s", key("Window:"), restart.window)?;
+        }
+        if let Some(ref shutdown) = self.runtime.shutdown {
+            let server_type = &self.project.server_type;
+            writeln!(f, "{}", title("Runtime → Shutdown"))?;
+            writeln!(f, "  {} {}", key("Command:"), shutdown.command(server_type))?;
+            writeln!(
+                f,
+                "  {} {}s",
+                key("Timeout:"),
+                shutdown.timeout(server_type).as_secs()
+            )?;
+            writeln!(f, "  {} {}s", key("Term Timeout:"), shutdown.term_timeout)?;
+            if shutdown.countdown > 0 {
+                writeln!(f, "  {} {}s", key("Countdown:"), shutdown.countdown)?;
+            }
+        }
 
         // === Backup ===
         writeln!(f, "{}", title("Backup"))?;
@@ -319,6 +862,85 @@ impl Display for Config {
             )?;
         }
 
+        if let Some(ref retention) = self.backup.retention {
+            writeln!(f, "  {}", "[Retention]".bright_cyan())?;
+            let rules = [
+                ("Keep Last:", retention.keep_last),
+                ("Keep Hourly:", retention.keep_hourly),
+                ("Keep Daily:", retention.keep_daily),
+                ("Keep Weekly:", retention.keep_weekly),
+                ("Keep Monthly:", retention.keep_monthly),
+            ];
+            for (name, value) in rules {
+                if let Some(value) = value {
+                    writeln!(f, "    {} {}", key(name), value)?;
+                }
+            }
+            if !retention.keep_tags.is_empty() {
+                writeln!(
+                    f,
+                    "    {} {}",
+                    key("Keep Tags:"),
+                    retention.keep_tags.join(", ")
+                )?;
+            }
+            if let Some(max_size) = retention.max_size.filter(|x| *x > 0) {
+                writeln!(f, "    {} {} MB", key("Max Size:"), max_size)?;
+            }
+        }
+
+        if let Some(ref password) = self.backup.password {
+            let source = match password {
+                BackupPassword { value: Some(_), .. } => "value".to_string(),
+                BackupPassword { env: Some(env), .. } => format!("${}", env),
+                BackupPassword {
+                    file: Some(file), ..
+                } => file.display().to_string(),
+                BackupPassword {
+                    command: Some(command),
+                    ..
+                } => format!("`{}`", command),
+                _ => "none".to_string(),
+            };
+            writeln!(f, "  {} {}", key("Password:"), source)?;
+        }
+        for (name, repo) in [
+            ("World", &self.backup.repository.world),
+            ("Other", &self.backup.repository.other),
+        ] {
+            if let Some(repo) = repo {
+                writeln!(f, "  {}", format!("[{} Repository]", name).bright_cyan())?;
+                writeln!(f, "    {} {}", key("Location:"), repo.backend.repository)?;
+                if let Some(ref hot) = repo.backend.repo_hot {
+                    writeln!(f, "    {} {}", key("Hot:"), hot)?;
+                }
+                for copy in &repo.copy {
+                    writeln!(f, "    {} {}", key("Copy:"), copy.repository)?;
+                }
+            }
+        }
+
+        // === Schedule ===
+        if !self.schedule.is_empty() {
+            writeln!(f, "{}", title("Schedule"))?;
+            for schedule in &self.schedule {
+                let action = match &schedule.action {
+                    ScheduleAction::Command { commands } => {
+                        format!("command {}", commands.join("; "))
+                    }
+                    ScheduleAction::Restart {
+                        countdown: Some(countdown),
+                    } => {
+                        format!("restart in {}s", countdown)
+                    }
+                    ScheduleAction::Restart { countdown: None } => "restart".to_string(),
+                    ScheduleAction::Backup => "backup".to_string(),
+                    ScheduleAction::Hook { command } => format!("hook `{}`", command),
+                };
+                writeln!(f, "  {} {}", key(&format!("{}:", schedule.cron)), action)?;
+            }
+        }
+
         // === Plugin Manage ===
         writeln!(f, "{}", title("Plugin Manage"))?;
         writeln!(
@@ -331,6 +953,23 @@ impl Display for Config {
                 "false".bright_red()
             }
         )?;
+        for plugin in &self.plugin {
+            writeln!(
+                f,
+                "  {} {:?} {}",
+                key(&format!("{}:", plugin.name)),
+                plugin.source,
+                plugin.version
+            )?;
+        }
+
+        // === Properties ===
+        if !self.properties.is_empty() {
+            writeln!(f, "{}", title("Properties"))?;
+            for (name, value) in &self.properties {
+                writeln!(f, "  {} {}", key(&format!("{}:", name)), value)?;
+            }
+        }
 
         writeln!(f, "{} {}", "╰─".bright_black(), "End of Config".dimmed())
     }
@@ -354,18 +993,17 @@ impl Java {
             return Ok(self.custom.clone());
         }
 
+        // 自动管理的 JDK 使用锁文件记录的版本
+        let (edition, version) = match Lock::load().ok().and_then(|x| x.java) {
+            Some(java) if JavaMode::Auto == self.mode => (java.edition, java.version),
+            _ => (self.edition.clone(), self.version),
+        };
+
         // 非自定义的 JDK
-        let java_home_path = PathBuf::from(format!(
-            "{}/java-{}-{}-{}-{}",
-            RUNTIME_DIR,
-            &self.version,
-            &self.edition,
-            std::env::consts::OS,
-            std::env::consts::ARCH
-        ));
+        let java_home_path = runtime_path(&edition, version);
         debug!("{:?}", java_home_path);
         if check_java(java_home_path.as_ref()) {
-            Ok(java_home_path.join("bin").join("java"))
+            Ok(java_binary(&java_home_path))
         } else {
             Err(Error::msg("Java cannot be found"))
         }
@@ -385,4 +1023,151 @@ mod tests {
         let toml_str = toml::to_string_pretty(&config).unwrap();
         println!("{}", toml_str);
     }
+
+    #[test]
+    fn test_retention() {
+        let backup: Backup = toml::from_str(
+            r#"
+            enable = true
+            world = true
+            other = false
+
+            [retention]
+            keep-last = 24
+            keep_daily = 7
+            keep_tags = ["Stop"]
+            max_size = 10240
+            "#,
+        )
+        .unwrap();
+        let retention = backup.retention.unwrap();
+        assert_eq!(retention.keep_last, Some(24));
+        assert_eq!(retention.keep_daily, Some(7));
+        assert_eq!(retention.keep_tags, vec!["Stop"]);
+        assert!(retention.has_keep_rules());
+        assert!(!Retention::default().has_keep_rules());
+    }
+
+    #[test]
+    fn test_backup_repository() {
+        let backup: Backup = toml::from_str(
+            r#"
+            enable = true
+            world = true
+            other = true
+
+            [repository.world]
+            repository = "opendal:s3"
+            options = { bucket = "pacmine", endpoint = "https://s3.example.com" }
+
+            [[repository.world.copy]]
+            repository = "/mnt/backup/world"
+            password = { file = "/etc/pacmine/password" }
+
+            [password]
+            env = "PACMINE_BACKUP_PASSWORD"
+            "#,
+        )
+        .unwrap();
+        let (world, other) = backup.targets();
+        let world = world.unwrap();
+        assert_eq!(world.backend.repository, "opendal:s3");
+        assert_eq!(world.backend.options["bucket"], "pacmine");
+        assert!(world.backend.local_path().is_none());
+        assert_eq!(
+            world.copy[0].local_path(),
+            Some(Path::new("/mnt/backup/world"))
+        );
+        assert_eq!(world.backend.password, backup.password);
+        assert_eq!(
+            world.copy[0].password.as_ref().unwrap().file,
+            Some(PathBuf::from("/etc/pacmine/password"))
+        );
+        let other = other.unwrap();
+        assert_eq!(other.backend.repository, format!("{}/other", BACKUP_DIR));
+        assert!(other.copy.is_empty());
+    }
+
+    #[test]
+    fn test_restart() {
+        let restart: Restart = toml::from_str(
+            r#"
+            policy = "on-failure"
+            max-retries = 3
+            backoff = 10
+            max_backoff = 60
+            "#,
+        )
+        .unwrap();
+        assert_eq!(restart.policy, RestartPolicy::OnFailure);
+        assert_eq!(restart.window, 600);
+        assert!(restart.should_restart(false));
+        assert!(!restart.should_restart(true));
+        assert_eq!(restart.delay(0), Duration::from_secs(10));
+        assert_eq!(restart.delay(2), Duration::from_secs(40));
+        assert_eq!(restart.delay(3), Duration::from_secs(60));
+        assert_eq!(restart.delay(100), Duration::from_secs(60));
+        assert!(!Restart::default().should_restart(false));
+    }
+
+    #[test]
+    fn test_schedule() {
+        #[derive(Deserialize)]
+        struct Schedules {
+            schedule: Vec<Schedule>,
+        }
+        let schedules: Schedules = toml::from_str(
+            r#"
+            [[schedule]]
+            cron = "0 0 4 * * *"
+            action = "restart"
+            countdown = 300
+
+            [[schedule]]
+            cron = "0 */30 * * * *"
+            action = "command"
+            commands = ["save-all", "say Saved"]
+
+            [[schedule]]
+            cron = "0 0 * * * *"
+            action = "backup"
+            "#,
+        )
+        .unwrap();
+        let actions: Vec<_> = schedules.schedule.into_iter().map(|x| x.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                ScheduleAction::Restart {
+                    countdown: Some(300)
+                },
+                ScheduleAction::Command {
+                    commands: vec!["save-all".to_string(), "say Saved".to_string()]
+                },
+                ScheduleAction::Backup,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let shutdown: Shutdown = toml::from_str(
+            r#"
+            timeout = 120
+            countdown = 30
+            "#,
+        )
+        .unwrap();
+        assert_eq!(shutdown.command(&ServerType::Paper), "stop");
+        assert_eq!(shutdown.command(&ServerType::Velocity), "end");
+        assert_eq!(
+            shutdown.timeout(&ServerType::Paper),
+            Duration::from_secs(120)
+        );
+        assert_eq!(shutdown.term_timeout, 10);
+        assert_eq!(shutdown.countdown_marks(), vec![30, 10, 5, 4, 3, 2, 1]);
+        let shutdown = Shutdown::default();
+        assert_eq!(shutdown.timeout(&ServerType::BDS), Duration::from_secs(30));
+        assert!(shutdown.countdown_marks().is_empty());
+    }
 }
@@ -0,0 +1,374 @@
+use crate::project_manager::events::{Event, LogParser};
+use crate::project_manager::state::{ServerState, Status, is_ready};
+use crate::project_manager::tools::ServerType;
+use crate::project_manager::{HISTORY_LINES, SAVE_TIMEOUT};
+use anyhow::Error;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Mutex, broadcast, watch};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Java 版服务端保存完成时输出的内容
+const SAVED_MESSAGE: &str = "Saved the game";
+/// BDS 保存完成、可以复制文件时输出的内容，下一行为文件列表
+const BDS_READY_MESSAGE: &str = "Files are now ready to be copied";
+
+/// 暂停保存后备份世界的方式
+#[derive(Debug, PartialEq)]
+pub enum Held {
+    /// 直接备份存档目录
+    Saved,
+    /// 仅备份 BDS 报告的文件，并截断到对应的长度，路径相对于存档目录
+    Files(Vec<(PathBuf, u64)>),
+}
+
+/// 服务端控制台，连接运行中的服务端进程，供备份等任务发送命令和读取输出
+pub struct Console {
+    /// 服务端类型
+    server_type: ServerType,
+    /// 服务端进程的标准输入，未运行时为空
+    stdin: Mutex<Option<ChildStdin>>,
+    /// 服务端输出的每一行
+    output: broadcast::Sender<String>,
+    /// 服务端最近输出的内容
+    history: std::sync::Mutex<VecDeque<String>>,
+    /// 服务端是否正在运行
+    running: watch::Sender<bool>,
+    /// 正在进行的需要暂停自动保存的备份数量
+    holds: AtomicUsize,
+    /// 是否请求了重启
+    restart: watch::Sender<bool>,
+    /// 重启前的倒计时，为空时使用 `[runtime.shutdown]` 的倒计时
+    countdown: std::sync::Mutex<Option<u64>>,
+    /// 服务端的运行状态
+    status: watch::Sender<Status>,
+    /// 解析服务端输出的日志事件
+    parser: std::sync::Mutex<LogParser>,
+    /// 服务端输出中解析出的事件
+    events: broadcast::Sender<Event>,
+}
+
+impl Console {
+    /// 创建未连接的控制台
+    pub fn new(server_type: ServerType) -> Arc<Self> {
+        Arc::new(Console {
+            server_type: server_type.clone(),
+            stdin: Mutex::new(None),
+            output: broadcast::channel(256).0,
+            history: std::sync::Mutex::new(VecDeque::with_capacity(HISTORY_LINES)),
+            running: watch::channel(false).0,
+            holds: AtomicUsize::new(0),
+            restart: watch::channel(false).0,
+            countdown: std::sync::Mutex::new(None),
+            status: watch::channel(Status::default()).0,
+            parser: std::sync::Mutex::new(LogParser::new(server_type.clone())),
+            events: broadcast::channel(256).0,
+        })
+    }
+
+    /// 连接到启动的服务端进程
+    pub async fn attach(&self, stdin: ChildStdin) {
+        self.history.lock().unwrap().clear();
+        self.parser.lock().unwrap().reset();
+        *self.stdin.lock().await = Some(stdin);
+        self.running.send_replace(true);
+    }
+
+    /// 服务端进程退出后断开连接
+    pub async fn detach(&self) {
+        *self.stdin.lock().await = None;
+        self.holds.store(0, Ordering::SeqCst);
+        self.restart.send_replace(false);
+        self.running.send_replace(false);
+    }
+
+    /// 服务端类型
+    pub fn server_type(&self) -> &ServerType {
+        &self.server_type
+    }
+
+    /// 服务端是否正在运行
+    pub fn is_running(&self) -> bool {
+        *self.running.borrow()
+    }
+
+    /// 服务端的运行状态
+    pub fn status(&self) -> Status {
+        self.status.borrow().clone()
+    }
+
+    /// 进入新的状态
+    pub fn set_state(&self, state: ServerState) {
+        self.status.send_if_modified(|status| {
+            if status.state == state {
+                return false;
+            }
+            info!("Server state: {:?} -> {:?}", status.state, state);
+            status.transition(state);
+            true
+        });
+    }
+
+    /// 等待服务端就绪，超时或启动失败时返回 false
+    pub async fn wait_ready(&self, timeout: Duration) -> bool {
+        let mut status = self.status.subscribe();
+        matches!(
+            tokio::time::timeout(timeout, status.wait_for(|x| x.state != ServerState::Starting)).await,
+            Ok(Ok(status)) if status.state == ServerState::Running
+        )
+    }
+
+    /// 等待服务端进程退出
+    pub async fn wait_stopped(&self) {
+        let _ = self.running.subscribe().wait_for(|running| !running).await;
+    }
+
+    /// 请求停止并重新启动服务端
+    pub fn request_restart(&self, countdown: Option<u64>) -> Result<(), Error> {
+        if !self.is_running() {
+            return Err(Error::msg("The server is not running"));
+        }
+        *self.countdown.lock().unwrap() = countdown;
+        self.restart.send_replace(true);
+        Ok(())
+    }
+
+    /// 等待重启请求，返回请求的倒计时
+    pub async fn restart_requested(&self) -> Option<u64> {
+        let _ = self.restart.subscribe().wait_for(|restart| *restart).await;
+        self.countdown.lock().unwrap().take()
+    }
+
+    /// 向服务端发送一行命令
+    pub async fn send(&self, command: &str) -> Result<(), Error> {
+        let mut stdin = self.stdin.lock().await;
+        let stdin = stdin
+            .as_mut()
+            .ok_or(Error::msg("The server is not running"))?;
+        stdin
+            .write_all(command.trim_end_matches(['\r', '\n']).as_bytes())
+            .await?;
+        stdin.write_all(b"\n").await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    /// 发布服务端输出的一行
+    pub fn publish(&self, line: &str) {
+        let mut history = self.history.lock().unwrap();
+        if history.len() == HISTORY_LINES {
+            history.pop_front();
+        }
+        history.push_back(line.to_string());
+        drop(history);
+        if self.status.borrow().state == ServerState::Starting && is_ready(&self.server_type, line)
+        {
+            self.set_state(ServerState::Running);
+        }
+        let event = self.parser.lock().unwrap().parse(line);
+        if let Some(event) = event {
+            let _ = self.events.send(event);
+        }
+        // 没有订阅者时忽略
+        let _ = self.output.send(line.to_string());
+    }
+
+    /// 服务端最近输出的内容，重新连接时清空
+    pub fn history(&self) -> Vec<String> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// 订阅服务端的输出
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.output.subscribe()
+    }
+
+    /// 订阅服务端输出中解析出的事件
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// 发送命令并等待包含 `expect` 的输出
+    pub async fn execute(
+        &self,
+        command: &str,
+        expect: &str,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        // 先订阅，避免错过命令的输出
+        let mut output = self.subscribe();
+        self.send(command).await?;
+        wait_for(&mut output, expect, timeout)
+            .await
+            .map_err(|e| Error::msg(format!("`{}`: {}", command, e)))
+    }
+
+    /// 暂停自动保存并将世界写入磁盘，返回 `Some` 时需要在复制存档后调用 `resume_saving`
+    pub async fn hold_saving(&self) -> Option<Held> {
+        if !self.is_running() || self.server_type.is_proxy() {
+            return None;
+        }
+        let bedrock = self.server_type == ServerType::BDS;
+        // 多个备份同时进行时只暂停一次
+        if self.holds.fetch_add(1, Ordering::SeqCst) == 0
+            && let Err(e) = self
+                .send(if bedrock { "save hold" } else { "save-off" })
+                .await
+        {
+            warn!("Failed to pause saving: {}", e);
+            self.holds.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        let timeout = Duration::from_secs(SAVE_TIMEOUT);
+        if bedrock {
+            match self.query_files(timeout).await {
+                Ok(files) => return Some(Held::Files(files)),
+                Err(e) => warn!("{}, back up the world anyway", e),
+            }
+        } else {
+            match self.execute("save-all flush", SAVED_MESSAGE, timeout).await {
+                Ok(_) => info!("The world has been saved"),
+                Err(e) => warn!("{}, back up the world anyway", e),
+            }
+        }
+        Some(Held::Saved)
+    }
+
+    /// 反复查询 BDS 的保存状态，直到可以复制文件
+    async fn query_files(&self, timeout: Duration) -> Result<Vec<(PathBuf, u64)>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut output = self.subscribe();
+            self.send("save query").await?;
+            // 未完成时输出 "A previous save has not been completed."，稍后重试
+            if wait_for(&mut output, BDS_READY_MESSAGE, Duration::from_secs(1))
+                .await
+                .is_ok()
+            {
+                // 文件列表为提示的下一行
+                let line = wait_for(&mut output, "", Duration::from_secs(1)).await?;
+                let files = parse_saved_files(&line);
+                if files.is_empty() {
+                    return Err(Error::msg(format!(
+                        "The file list cannot be parsed: {}",
+                        line.trim()
+                    )));
+                }
+                return Ok(files);
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::msg(format!(
+                    "The world was not ready to be copied within {}s",
+                    timeout.as_secs()
+                )));
+            }
+        }
+    }
+
+    /// 所有备份完成后恢复自动保存
+    pub async fn resume_saving(&self) {
+        if self
+            .holds
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| x.checked_sub(1))
+            != Ok(1)
+        {
+            return;
+        }
+        let command = if self.server_type == ServerType::BDS {
+            "save resume"
+        } else {
+            "save-on"
+        };
+        if let Err(e) = self.send(command).await {
+            warn!("Failed to resume saving: {}", e);
+        }
+    }
+}
+
+/// 等待包含 `expect` 的输出，超时或服务端退出时返回错误
+async fn wait_for(
+    output: &mut broadcast::Receiver<String>,
+    expect: &str,
+    timeout: Duration,
+) -> Result<String, Error> {
+    tokio::time::timeout(timeout, async {
+        loop {
+            match output.recv().await {
+                Ok(line) if line.contains(expect) => return Ok(line),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Err(Error::msg("The server has exited")),
+            }
+        }
+    })
+    .await
+    .map_err(|_| {
+        Error::msg(format!(
+            "No response from the server within {}s",
+            timeout.as_secs()
+        ))
+    })?
+}
+
+/// 解析 BDS `save query` 输出的文件列表，格式为 `路径:长度, 路径:长度`
+fn parse_saved_files(line: &str) -> Vec<(PathBuf, u64)> {
+    line.trim()
+        .split(", ")
+        .filter_map(|x| {
+            let (path, length) = x.rsplit_once(':')?;
+            Some((PathBuf::from(path), length.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for() {
+        let console = Console::new(ServerType::Vanilla);
+        let mut output = console.subscribe();
+        console.publish("[12:00:00] [Server thread/INFO]: Automatic saving is now disabled\n");
+        console.publish("[12:00:01] [Server thread/INFO]: Saved the game\n");
+        let line = wait_for(&mut output, SAVED_MESSAGE, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(line.ends_with("Saved the game\n"));
+        assert!(
+            wait_for(&mut output, SAVED_MESSAGE, Duration::from_millis(10))
+                .await
+                .is_err()
+        );
+        // 未连接时不暂停自动保存
+        assert_eq!(console.hold_saving().await, None);
+        assert!(console.send("save-on").await.is_err());
+        assert!(console.request_restart(None).is_err());
+        // 启动中的服务端输出就绪的内容后进入运行状态
+        console.set_state(ServerState::Starting);
+        console.publish("[12:00:02] [Server thread/INFO]: Done (2.5s)! For help, type \"help\"\n");
+        assert!(console.wait_ready(Duration::from_millis(10)).await);
+    }
+
+    #[test]
+    fn test_parse_saved_files() {
+        let files = parse_saved_files(
+            "Bedrock level/db/000005.ldb:1024, Bedrock level/db/CURRENT:16, Bedrock level/level.dat:2533\n",
+        );
+        assert_eq!(
+            files,
+            vec![
+                (PathBuf::from("Bedrock level/db/000005.ldb"), 1024),
+                (PathBuf::from("Bedrock level/db/CURRENT"), 16),
+                (PathBuf::from("Bedrock level/level.dat"), 2533),
+            ]
+        );
+        assert!(parse_saved_files("A previous save has not been completed.").is_empty());
+    }
+}
@@ -1,23 +1,182 @@
-use crate::project_manager::config::ServerType;
+use crate::project_manager::config::{JavaMode, JavaType, ServerType};
 use crate::project_manager::info::{ConfigErr, get_info};
 use crate::project_manager::tools::{
-    PaperProject, VersionInfo, VersionType, analyze_je_game, get_mime_type,
+    BDS_EXECUTABLE, analyze_bds_game, analyze_je_game, analyze_loader_version, get_mime_type,
+    print_supported_versions, provider_for,
 };
 use crate::project_manager::{
-    BACKUP_DIR, CACHE_DIR, CONFIG_FILE, Config, FOLIA_PROJECT_API, LEAVES_PROJECT_API, LOG_DIR,
-    PAPER_PROJECT_API, PURPUR_PROJECT_API, RUNTIME_DIR, WORK_DIR,
+    BACKUP_DIR, CACHE_DIR, CONFIG_FILE, Config, LOCK_FILE, LOG_DIR, RUNTIME_DIR, TEMPLATE_DIR,
+    WORK_DIR,
 };
 use anyhow::Error;
+use clap::Args;
 use colored::Colorize;
+use home::home_dir;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 pub const DIR_LIST: [&str; 5] = [WORK_DIR, CACHE_DIR, BACKUP_DIR, RUNTIME_DIR, LOG_DIR];
 
+/// 创建项目的选项，缺省的选项在交互模式下询问用户
+#[derive(Args, Debug, Default, Clone)]
+pub struct CreateOptions {
+    /// The name of the project
+    #[arg(long)]
+    pub name: Option<String>,
+    /// The type of the server, such as vanilla, paper, fabric or bds
+    #[arg(long = "type", value_name = "TYPE")]
+    pub server_type: Option<ServerType>,
+    /// The game version, "latest" for the latest version
+    #[arg(long = "version", value_name = "VERSION")]
+    pub version: Option<String>,
+    /// The mod loader version of mod servers
+    #[arg(long)]
+    pub loader: Option<String>,
+    /// The executable file of the server
+    #[arg(long)]
+    pub execute: Option<PathBuf>,
+    /// The Java to use: "auto", a Java version or the path of a custom Java
+    #[arg(long)]
+    pub java: Option<String>,
+    /// The initial heap size of the JVM in MB
+    #[arg(long)]
+    pub xms: Option<usize>,
+    /// The maximum heap size of the JVM in MB
+    #[arg(long)]
+    pub xmx: Option<usize>,
+    /// Enable or disable the backup
+    #[arg(long, value_name = "BOOL")]
+    pub backup: Option<bool>,
+    /// A template directory, a PacMine.toml file, or the name of a template in ~/.pacmine/templates
+    #[arg(long)]
+    pub template: Option<String>,
+    /// Do not ask questions, use the default values for missing options
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+/// 项目模板，由 PacMine.toml 和需要复制到项目中的文件组成
+struct Template {
+    /// 模板中的配置，可以只包含部分配置
+    config: toml::Table,
+    /// 模板目录，单个配置文件的模板不存在
+    dir: Option<PathBuf>,
+}
+
+impl Template {
+    /// 读取模板，`name` 为模板目录、配置文件或全局模板的名称
+    fn load(name: &str) -> Result<Self, Error> {
+        let mut path = PathBuf::from(name);
+        if !path.exists() {
+            path = home_dir()
+                .ok_or(Error::msg("Could not get the home directory"))?
+                .join(TEMPLATE_DIR)
+                .join(name);
+        }
+        let (config_file, dir) = if path.is_dir() {
+            (path.join(CONFIG_FILE), Some(path))
+        } else if path.is_file() {
+            (path, None)
+        } else {
+            return Err(Error::msg(format!("The template {} cannot be found", name)));
+        };
+        let config = if config_file.is_file() {
+            toml::from_str(&fs::read_to_string(&config_file)?)?
+        } else {
+            toml::Table::new()
+        };
+        Ok(Template { config, dir })
+    }
+
+    /// 以模板中的配置覆盖默认配置
+    fn seed(&self) -> Result<Config, Error> {
+        let mut config = toml::Table::try_from(Config::default())?;
+        merge_table(&mut config, self.config.clone());
+        // 创建日期不使用模板中的值
+        if let Some(toml::Value::Table(project)) = config.get_mut("project") {
+            project.insert(
+                "birthday".to_string(),
+                toml::Value::try_from(chrono::Utc::now())?,
+            );
+        }
+        Ok(config.try_into()?)
+    }
+
+    /// 模板中的项目信息作为缺省的选项
+    fn fill_options(&self, options: &mut CreateOptions) -> Result<(), Error> {
+        let Some(project) = self.config.get("project").and_then(|x| x.as_table()) else {
+            return Ok(());
+        };
+        let text = |key: &str| project.get(key).and_then(|x| x.as_str()).map(String::from);
+        options.name = options.name.take().or(text("name"));
+        options.version = options.version.take().or(text("version"));
+        options.loader = options.loader.take().or(text("loader_version"));
+        options.execute = options
+            .execute
+            .take()
+            .or(text("execute").map(PathBuf::from));
+        if options.server_type.is_none()
+            && let Some(server_type) = project.get("server_type")
+        {
+            options.server_type = Some(server_type.clone().try_into()?);
+        }
+        Ok(())
+    }
+
+    /// 复制模板目录中的文件，跳过配置文件、锁文件和已存在的文件
+    fn copy_files(&self) -> Result<(), Error> {
+        if let Some(dir) = &self.dir {
+            copy_dir(dir, Path::new("."))?;
+        }
+        Ok(())
+    }
+}
+
+/// 递归合并 TOML 表，`other` 中的值优先
+fn merge_table(base: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(other)) => merge_table(base, other),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 将模板目录中的文件复制到项目
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if [CONFIG_FILE, LOCK_FILE, WORK_DIR]
+            .iter()
+            .any(|x| name == *x)
+            && to == Path::new(".")
+        {
+            continue;
+        }
+        let target = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_dir(&entry.path(), &target)?;
+        } else if target.exists() {
+            warn!(
+                "{} already exists, skip the template file",
+                target.display()
+            );
+        } else {
+            debug!("Copy {} from the template", target.display());
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
 /// 初始化配置文件
-pub fn create_project() {
+pub fn create_project(options: &CreateOptions) {
     // 判断配置是否完整存在
     let config_err = match get_info() {
         // 项目已存在
@@ -34,27 +193,43 @@ pub fn create_project() {
         return;
     }
 
-    // 项目不存在，尝试创建
-    // 判断是否有 server 文件
-    let config = if get_mime_type(&PathBuf::from("server.jar")) == "application/zip" {
-        // 尝试分析 server.jar 成功则根据已有 jar 创建
-        create_config_jar_file(PathBuf::from_str("server.jar").unwrap()).unwrap_or_else(|e| {
-            warn!("{:?}", e);
-            create_config_empty()
-        })
-    } else if get_mime_type(&PathBuf::from("bedrock_server")) == "application/x-executable" {
-        // 尝试分析 server 成功则根据已有二进制文件创建，否则按照空项目处理
-        error!("Bedrock Edition version is not supported for the time being!");
-        todo!()
-    } else if get_mime_type(&PathBuf::from("bedrock_server.exe"))
-        == "application/vnd.microsoft.portable-executable"
+    // 读取模板，模板中的配置作为基础配置
+    let mut options = options.clone();
+    let template = match options.template.as_deref().map(Template::load).transpose() {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+    let seed = || match &template {
+        Some(template) => template.seed(),
+        None => Ok(Config::default()),
+    };
+    if let Some(e) = template
+        .as_ref()
+        .map(|x| x.fill_options(&mut options))
+        .transpose()
+        .and_then(|_| seed())
+        .err()
     {
-        // 尝试分析 server.exe 成功则根据已有二进制文件创建，否则按照空项目处理
-        error!("Bedrock Edition version is not supported for the time being!");
-        todo!()
-    } else {
-        // 按空项目创建
-        create_config_empty()
+        error!("The template cannot be used: {}", e);
+        return;
+    }
+
+    // 项目不存在，尝试创建
+    let config = create_config(&options, &seed);
+    // 应用运行环境和备份的选项
+    let config = config.and_then(|mut config| {
+        apply_options(&mut config, &options)?;
+        Ok(config)
+    });
+    let config = match config {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
     };
 
     // 初始化项目
@@ -66,19 +241,136 @@ pub fn create_project() {
     for i in DIR_LIST {
         fs::create_dir(i).expect("Directory cannot be created!")
     }
+    // 复制模板中的文件
+    if let Some(template) = template {
+        template
+            .copy_files()
+            .expect("The template files cannot be copied!");
+    }
 
     info!("{}", "The project has been successfully created".green())
 }
 
-/// 询问用户配置信息并创建配置文件
-fn create_config_empty() -> Config {
-    // 创建基本配置
-    let mut new_config = Config::default();
+/// 根据目录中已有的服务端创建配置，没有可用的服务端时按空项目创建，`seed` 为基础配置
+fn create_config(
+    options: &CreateOptions,
+    seed: &dyn Fn() -> Result<Config, Error>,
+) -> Result<Config, Error> {
+    // 判断是否有 server 文件，模组服务端的启动器优先于 server.jar
+    let launcher = ["fabric-server-launch.jar", "quilt-server-launch.jar"]
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|x| get_mime_type(x) == "application/zip");
+    let detected = if let Some(launcher) = launcher {
+        // 尝试分析启动器成功则根据已有 jar 创建
+        create_config_jar_file(launcher, seed()?, options)
+    } else if get_mime_type(&PathBuf::from("server.jar")) == "application/zip" {
+        // 尝试分析 server.jar 成功则根据已有 jar 创建
+        create_config_jar_file(PathBuf::from_str("server.jar").unwrap(), seed()?, options)
+    } else if get_mime_type(&PathBuf::from("bedrock_server")) == "application/x-executable" {
+        // 尝试分析 bedrock_server 成功则根据已有二进制文件创建
+        create_config_bds_file(PathBuf::from("bedrock_server"), seed()?, options)
+    } else if get_mime_type(&PathBuf::from("bedrock_server.exe"))
+        == "application/vnd.microsoft.portable-executable"
+    {
+        // 尝试分析 bedrock_server.exe 成功则根据已有二进制文件创建
+        create_config_bds_file(PathBuf::from("bedrock_server.exe"), seed()?, options)
+    } else {
+        // 按空项目创建
+        return create_config_empty(seed()?, options);
+    };
+    match detected {
+        Ok(mut config) => {
+            check_detected(&mut config, options)?;
+            Ok(config)
+        }
+        // 分析失败时按照空项目处理
+        Err(e) => {
+            warn!("{:?}", e);
+            create_config_empty(seed()?, options)
+        }
+    }
+}
 
-    // 获取项目名称
-    println!("Enter the name of this project:");
-    new_config.project.name = get_input().trim().to_string();
+/// 检查选项与已有服务端是否一致，不一致时返回错误，避免创建的项目与要求的不同
+fn check_detected(config: &mut Config, options: &CreateOptions) -> Result<(), Error> {
+    let project = &mut config.project;
+    let conflict = |option: &str, value: &str, detected: &str| {
+        Err(Error::msg(format!(
+            "{} {} does not match the existing server {} ({}), remove the option or the server file",
+            option,
+            value,
+            project.execute.display(),
+            detected
+        )))
+    };
+    if let Some(server_type) = &options.server_type
+        && server_type != &project.server_type
+    {
+        return conflict(
+            "--type",
+            &format!("{:?}", server_type),
+            &format!("{:?}", project.server_type),
+        );
+    }
+    match options.version.as_deref() {
+        // 不联网无法判断是否为最新版本，使用已有的服务端
+        Some("latest") => warn!(
+            "Use the existing server {} {}",
+            project.execute.display(),
+            project.version
+        ),
+        Some(version) if version != project.version => {
+            return conflict("--version", version, &project.version);
+        }
+        _ => (),
+    }
+    if let Some(loader) = &options.loader {
+        match &project.loader_version {
+            Some(detected) if detected != loader => {
+                return conflict("--loader", loader, detected);
+            }
+            Some(_) => (),
+            // 无法从启动器获取加载器版本时使用选项中的版本
+            None => project.loader_version = Some(loader.clone()),
+        }
+    }
+    Ok(())
+}
+
+/// 应用与服务端无关的选项
+fn apply_options(config: &mut Config, options: &CreateOptions) -> Result<(), Error> {
+    let java = &mut config.runtime.java;
+    match options.java.as_deref() {
+        None => (),
+        Some("auto") => java.mode = JavaMode::Auto,
+        // 指定版本的 OpenJDK
+        Some(v) if v.parse::<usize>().is_ok() => {
+            java.mode = JavaMode::Manual;
+            java.edition = JavaType::OpenJDK;
+            java.version = v.parse()?;
+        }
+        // 自定义的 Java
+        Some(v) => {
+            java.mode = JavaMode::Manual;
+            java.edition = JavaType::Custom;
+            java.custom = PathBuf::from(v);
+        }
+    }
+    if let Some(xms) = options.xms {
+        java.xms = xms;
+    }
+    if let Some(xmx) = options.xmx {
+        java.xmx = xmx;
+    }
+    if let Some(backup) = options.backup {
+        config.backup.enable = backup;
+    }
+    Ok(())
+}
 
+/// 询问用户选择服务端类型
+fn select_server_type() -> ServerType {
     // 列出服务端类型
     println!("Select the type of the server:");
     println!("1: Vanilla(Official)");
@@ -86,8 +378,15 @@ fn create_config_empty() -> Config {
     println!("3: PurpurMC");
     println!("4: LeavesMC");
     println!("5: Bedrock Dedicated Server(Official)");
+    println!("6: Fabric");
+    println!("7: Quilt");
+    println!("8: Forge");
+    println!("9: NeoForge");
+    println!("10: Velocity(Proxy)");
+    println!("11: Waterfall(Proxy)");
+    println!("12: BungeeCord(Proxy)");
     println!("0: Other Server");
-    new_config.project.server_type = loop {
+    loop {
         // 获取输入
         let input = get_input().trim().parse::<usize>();
         // 解析输入为 usize
@@ -105,38 +404,137 @@ fn create_config_empty() -> Config {
             3 => break ServerType::Purpur,
             4 => break ServerType::Leaves,
             5 => break ServerType::BDS,
+            6 => break ServerType::Fabric,
+            7 => break ServerType::Quilt,
+            8 => break ServerType::Forge,
+            9 => break ServerType::NeoForge,
+            10 => break ServerType::Velocity,
+            11 => break ServerType::Waterfall,
+            12 => break ServerType::BungeeCord,
             0 => break ServerType::Other,
             _ => {
                 println!("Please select within the range.");
                 continue;
             }
         };
+    }
+}
+
+/// 获取项目名称，非交互模式下缺省时使用目录名称
+fn project_name(options: &CreateOptions) -> String {
+    match &options.name {
+        Some(name) => name.clone(),
+        None if options.yes => std::env::current_dir()
+            .ok()
+            .and_then(|x| x.file_name().map(|x| x.to_string_lossy().to_string()))
+            .unwrap_or_else(|| Config::default().project.name),
+        None => {
+            println!("Enter the name of this project:");
+            get_input().trim().to_string()
+        }
+    }
+}
+
+/// 询问用户配置信息并创建配置文件，已经通过选项指定的配置不再询问
+fn create_config_empty(mut new_config: Config, options: &CreateOptions) -> Result<Config, Error> {
+    // 获取项目名称
+    new_config.project.name = project_name(options);
+    new_config.project.loader_version = options.loader.clone();
+
+    // 获取服务端类型，非交互模式下缺省时使用 Vanilla
+    new_config.project.server_type = match &options.server_type {
+        Some(server_type) => server_type.clone(),
+        None if options.yes => ServerType::Vanilla,
+        None => select_server_type(),
     };
 
     // 根据服务端类型设置可执行文件
-    new_config.project.execute = match new_config.project.server_type {
+    new_config.project.execute = match (&options.execute, &new_config.project.server_type) {
+        // 指定的可执行文件
+        (Some(execute), _) => execute.clone(),
         // 自定义的可执行文件
-        ServerType::Other => {
+        (None, ServerType::Other) if options.yes => PathBuf::from("server.jar"),
+        (None, ServerType::Other) => {
             println!("Enter the name of the executable file");
-            get_input().parse().unwrap()
+            get_input().trim().parse()?
         }
         // BE 版可执行文件
-        ServerType::BDS => PathBuf::from("server"),
+        (None, ServerType::BDS) => PathBuf::from(BDS_EXECUTABLE),
+        // 模组服务端的启动器
+        (None, ServerType::Fabric) => PathBuf::from("fabric-server-launch.jar"),
+        (None, ServerType::Quilt) => PathBuf::from("quilt-server-launch.jar"),
+        // Forge 类服务端的参数文件
+        (None, ServerType::Forge | ServerType::NeoForge) => PathBuf::from("server_args.txt"),
         // JE 版可执行文件
         _ => PathBuf::from("server.jar"),
     };
 
-    // 获取并验证版本号
-    if new_config.project.server_type.eq(&ServerType::BDS) {
-        // 先检查基岩版
+    // 没有服务端来源的 Other 类型直接使用指定的版本
+    if new_config.project.server_type == ServerType::Other && new_config.provider.is_none() {
+        if let Some(version) = &options.version {
+            new_config.project.version = version.clone();
+        }
+        return Ok(new_config);
+    }
+    let provider = provider_for(&new_config);
 
-        println!("Set the game version."); // 提示信息
-        // 确保设置格式正确的版本号
-        loop {
-            let input = get_input().trim().to_string(); //输入
-            match VersionInfo::get_version_info(&input, ServerType::BDS) {
+    // 通过选项指定的版本，检查失败时直接返回错误
+    match options.version.as_deref() {
+        Some("latest") => {
+            new_config.project.version = provider.latest_version(None)?;
+            return Ok(new_config);
+        }
+        Some(version) => {
+            let v = provider.version_info(version)?;
+            if let Some(supported) = provider.versions()?
+                && !supported.contains(&v.name)
+            {
+                print_supported_versions(&supported);
+                return Err(Error::msg(format!("Unsupported version {}", v.name)));
+            }
+            new_config.project.version_type = v.version_type;
+            new_config.project.version = v.name;
+            return Ok(new_config);
+        }
+        None if options.yes => {
+            new_config.project.version = provider.latest_version(None)?;
+            return Ok(new_config);
+        }
+        None => (),
+    }
+
+    // 获取服务端支持的版本，获取失败时不做检查
+    let supported = provider.versions().unwrap_or_else(|e| {
+        warn!("Failed to get the supported versions: {}", e);
+        None
+    });
+
+    println!("Set the game version. The default is the latest version."); // 提示信息
+    // 确保正确设置版本
+    loop {
+        let input = get_input().trim().to_string(); //输入
+        if input.is_empty() || &input == "latest" {
+            // 默认的最新版本
+
+            // version_type 默认已经为 Release
+            // 获取最新版本
+            new_config.project.version = provider.latest_version(None)?;
+            // 成功设置
+            break;
+        } else {
+            // 手动设置的版本
+
+            // 判断输入版本是否存在
+            match provider.version_info(&input) {
+                Ok(v) if supported.as_ref().is_some_and(|x| !x.contains(&v.name)) => {
+                    // 服务端不支持该版本
+                    print_supported_versions(supported.as_ref().unwrap());
+                    println!("{}", "Please re-enter the version".yellow());
+                    continue;
+                }
                 Ok(v) => {
                     // 成功设置
+                    new_config.project.version_type = v.version_type;
                     new_config.project.version = v.name;
                     break;
                 }
@@ -148,97 +546,57 @@ fn create_config_empty() -> Config {
                 }
             }
         }
-    } else if new_config.project.server_type.ne(&ServerType::Other) {
-        // 再检查Java版
-
-        println!("Set the game version. The default is the latest version."); // 提示信息
-        // 确保正确设置版本
-        loop {
-            let input = get_input().trim().to_string(); //输入
-            if input.is_empty() || &input == "latest" {
-                // 默认的最新版本
-
-                // version_type 默认已经为 Release
-                // 获取最新版本
-                new_config.project.version = match new_config.project.server_type {
-                    ServerType::Vanilla => VersionInfo::get_latest_version(VersionType::Release)
-                        .expect("Failed to get the latest version"),
-                    ServerType::Paper => PaperProject::fetch(PAPER_PROJECT_API)
-                        .expect("Failed to get the latest version")
-                        .versions
-                        .last()
-                        .expect("Failed to get the latest version")
-                        .to_string(),
-                    ServerType::Folia => PaperProject::fetch(FOLIA_PROJECT_API)
-                        .expect("Failed to get the latest version")
-                        .versions
-                        .last()
-                        .expect("Failed to get the latest version")
-                        .to_string(),
-                    ServerType::Purpur => PaperProject::fetch(PURPUR_PROJECT_API)
-                        .expect("Failed to get the latest version")
-                        .versions
-                        .last()
-                        .expect("Failed to get the latest version")
-                        .to_string(),
-                    ServerType::Leaves => PaperProject::fetch(LEAVES_PROJECT_API)
-                        .expect("Failed to get the latest version")
-                        .versions
-                        .last()
-                        .expect("Failed to get the latest version")
-                        .to_string(),
-                    ServerType::Other => String::new(),
-                    _ => unreachable!("不存在的服务端类型"),
-                };
-                // 成功设置
-                break;
-            } else {
-                // 手动设置的版本
-
-                // 判断输入版本是否存在
-                match VersionInfo::get_version_info(&input, new_config.project.server_type.clone())
-                {
-                    Ok(v) => {
-                        // 成功设置
-                        new_config.project.version_type = v.version_type;
-                        new_config.project.version = v.name;
-                        break;
-                    }
-                    Err(e) => {
-                        // 重新输入
-                        error!("{}", e);
-                        println!("{}", "Please re-enter the version".yellow());
-                        continue;
-                    }
-                }
-            }
-        }
     }
 
-    new_config
+    Ok(new_config)
 }
 
 /// 通过已有的 jar 服务端文件创建配置
-fn create_config_jar_file(server_file: PathBuf) -> Result<Config, Error> {
-    // 创建基本配置
-    let mut new_config = Config::default();
-
+fn create_config_jar_file(
+    server_file: PathBuf,
+    mut new_config: Config,
+    options: &CreateOptions,
+) -> Result<Config, Error> {
     // 解析 jar 文件获得版本信息
     let version_info = analyze_je_game(&server_file)?;
     // 设置版本信息
     new_config.project.version = version_info.name.clone();
     new_config.project.server_type = version_info.server_type.clone();
     new_config.project.version_type = version_info.version_type.clone();
+    // 模组服务端记录加载器版本
+    if let ServerType::Fabric | ServerType::Quilt = version_info.server_type {
+        new_config.project.loader_version = analyze_loader_version(&server_file);
+    }
+    new_config.project.execute = server_file;
 
     // 获取项目名称
-    println!("Enter the name of this project:");
-    new_config.project.name = get_input().trim().to_string();
+    new_config.project.name = project_name(options);
+
+    Ok(new_config)
+}
+
+/// 通过已有的 Bedrock Edition 服务端创建配置
+fn create_config_bds_file(
+    server_file: PathBuf,
+    mut new_config: Config,
+    options: &CreateOptions,
+) -> Result<Config, Error> {
+    // 解析服务端获得版本信息
+    let version_info = analyze_bds_game(&server_file)?;
+    // 设置版本信息
+    new_config.project.version = version_info.name;
+    new_config.project.server_type = ServerType::BDS;
+    new_config.project.version_type = version_info.version_type;
+    new_config.project.execute = server_file;
+
+    // 获取项目名称
+    new_config.project.name = project_name(options);
 
     Ok(new_config)
 }
 
 /// 获取一行输入，带有提示符 `>`
-fn get_input() -> String {
+pub(crate) fn get_input() -> String {
     // 初始化输入缓存
     let mut input_buffer = String::new();
     // 打印提示符
@@ -251,3 +609,63 @@ fn get_input() -> String {
         Err(_) => panic!("Unknown input error!"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_seed() {
+        let template = Template {
+            config: toml::from_str(
+                r#"
+                [project]
+                server_type = "Paper"
+                version = "1.21.1"
+
+                [runtime.java]
+                xmx = 4096
+                "#,
+            )
+            .unwrap(),
+            dir: None,
+        };
+        let config = template.seed().unwrap();
+        assert_eq!(config.runtime.java.xmx, 4096);
+        assert_eq!(config.runtime.java.mode, JavaMode::Auto);
+        let mut options = CreateOptions {
+            version: Some("1.20.4".to_string()),
+            ..Default::default()
+        };
+        template.fill_options(&mut options).unwrap();
+        assert_eq!(options.server_type, Some(ServerType::Paper));
+        assert_eq!(options.version.as_deref(), Some("1.20.4"));
+    }
+
+    #[test]
+    fn test_check_detected() {
+        let mut config = Config::default();
+        config.project.server_type = ServerType::Fabric;
+        config.project.version = "1.21.1".to_string();
+        config.project.execute = PathBuf::from("fabric-server-launch.jar");
+        let options = |server_type, version: &str, loader: Option<&str>| CreateOptions {
+            server_type,
+            version: Some(version.to_string()),
+            loader: loader.map(String::from),
+            ..Default::default()
+        };
+        assert!(check_detected(&mut config, &options(None, "latest", None)).is_ok());
+        assert!(check_detected(&mut config, &options(None, "1.20.4", None)).is_err());
+        assert!(
+            check_detected(
+                &mut config,
+                &options(Some(ServerType::Paper), "1.21.1", None)
+            )
+            .is_err()
+        );
+        // 无法获取加载器版本时使用选项中的版本
+        assert!(check_detected(&mut config, &options(None, "1.21.1", Some("0.16.9"))).is_ok());
+        assert_eq!(config.project.loader_version.as_deref(), Some("0.16.9"));
+        assert!(check_detected(&mut config, &options(None, "1.21.1", Some("0.16.5"))).is_err());
+    }
+}
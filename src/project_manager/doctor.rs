@@ -0,0 +1,389 @@
+use crate::project_manager::config::{BackupBackend, JavaMode, JavaType};
+use crate::project_manager::info::ConfigErr;
+use crate::project_manager::lock::Lock;
+use crate::project_manager::properties::checked_properties;
+use crate::project_manager::tools::backup::backup_check_repo;
+use crate::project_manager::tools::{
+    LaunchSpec, ServerProperties, ServerType, analyze_jar, check_java, property_value,
+    provider_for, runtime_path,
+};
+use crate::project_manager::{CONFIG_FILE, Config, PROPERTIES_FILE, get_info};
+use colored::Colorize;
+use std::fs;
+use std::net::{TcpListener, UdpSocket};
+use std::path::Path;
+
+/// 可用空间低于该值时发出警告，单位为 MB
+const MIN_FREE_DISK: u64 = 2048;
+
+/// 检查结果的状态
+#[derive(PartialEq)]
+enum Status {
+    /// 检查通过
+    Pass,
+    /// 可能存在问题，不影响运行
+    Warn,
+    /// 存在问题，服务端无法正常运行
+    Fail,
+}
+
+/// 单项检查的结果
+struct Diagnosis {
+    /// 检查项目
+    name: &'static str,
+    status: Status,
+    /// 检查结果说明
+    message: String,
+    /// 修复建议
+    fix: Option<String>,
+}
+
+impl Diagnosis {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Diagnosis {
+            name,
+            status: Status::Pass,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Diagnosis {
+            name,
+            status: Status::Warn,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Diagnosis {
+            name,
+            status: Status::Fail,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// 检查项目配置与实际的目录和主机环境，存在失败的检查项时返回 false
+pub fn doctor() -> bool {
+    let results = match get_info() {
+        Ok(config) => {
+            let mut results = vec![Diagnosis::pass(
+                "Configuration",
+                format!("{} is valid", CONFIG_FILE),
+            )];
+            results.extend(check_project(&config));
+            results
+        }
+        Err(ConfigErr::NotConfigured) => vec![Diagnosis::fail(
+            "Configuration",
+            "There is no project in the current directory",
+            "Run `pacmine init` to create a project",
+        )],
+        Err(ConfigErr::ConfigBroken) => vec![Diagnosis::fail(
+            "Configuration",
+            format!("{} or the .pacmine directory is damaged", CONFIG_FILE),
+            format!("Fix {} according to the error above", CONFIG_FILE),
+        )],
+    };
+
+    // 输出检查结果
+    for result in &results {
+        let mark = match result.status {
+            Status::Pass => "✔".green(),
+            Status::Warn => "!".yellow(),
+            Status::Fail => "✘".red(),
+        };
+        println!("  {} {:<14} {}", mark, result.name, result.message);
+        if let Some(fix) = &result.fix {
+            println!("      {} {}", "→".bright_black(), fix);
+        }
+    }
+    let failed = results.iter().filter(|x| x.status == Status::Fail).count();
+    let warned = results.iter().filter(|x| x.status == Status::Warn).count();
+    println!(
+        "{} failed, {} warnings, {} passed",
+        failed,
+        warned,
+        results.len() - failed - warned
+    );
+    failed == 0
+}
+
+/// 检查项目
+fn check_project(config: &Config) -> Vec<Diagnosis> {
+    let mut results = vec![check_core(config)];
+    // 直接运行的服务端不使用 Java 的设置
+    let native = provider_for(config).launch() == LaunchSpec::Native;
+    if !native {
+        results.extend(check_runtime(config));
+    }
+    results.push(match checked_properties(config) {
+        Ok(_) => Diagnosis::pass("Properties", "The [properties] section is valid"),
+        Err(e) => Diagnosis::fail(
+            "Properties",
+            e.to_string(),
+            format!("Fix the [properties] section in {}", CONFIG_FILE),
+        ),
+    });
+    results.push(check_eula(config));
+    results.push(check_port(config));
+    results.extend(check_backup(config));
+    #[cfg(target_family = "unix")]
+    results.extend(check_disk());
+    #[cfg(target_os = "linux")]
+    if !native {
+        results.extend(check_memory(config));
+    }
+    results
+}
+
+/// 检查服务端文件
+fn check_core(config: &Config) -> Diagnosis {
+    let execute = &config.project.execute;
+    if !execute.exists() {
+        return Diagnosis::fail(
+            "Server",
+            format!("{} does not exist", execute.display()),
+            "Run `pacmine install` to install the server",
+        );
+    }
+    if !provider_for(config).check(execute) {
+        return Diagnosis::fail(
+            "Server",
+            format!("{} cannot be used", execute.display()),
+            format!(
+                "Delete {} and run `pacmine install` to reinstall the server",
+                execute.display()
+            ),
+        );
+    }
+    // 直接运行的服务端记录的是压缩包的哈希值，不与锁文件对比
+    let native = provider_for(config).launch() == LaunchSpec::Native;
+    match Lock::load() {
+        Ok(lock) => match lock.core_for(config) {
+            Some(locked) if !native && !locked.verify(execute) => Diagnosis::warn(
+                "Server",
+                format!("{} differs from the lock file", execute.display()),
+                "Run `pacmine install` to restore the locked server, or `pacmine upgrade` to update the lock",
+            ),
+            _ => Diagnosis::pass(
+                "Server",
+                format!(
+                    "{:?} {} at {}",
+                    config.project.server_type,
+                    config.project.version,
+                    execute.display()
+                ),
+            ),
+        },
+        Err(e) => Diagnosis::fail(
+            "Server",
+            format!("The lock file cannot be read: {}", e),
+            "Delete the lock file and run `pacmine install` to recreate it",
+        ),
+    }
+}
+
+/// 检查 Java 环境以及与服务端的兼容性
+fn check_runtime(config: &Config) -> Vec<Diagnosis> {
+    let java = &config.runtime.java;
+    // 自定义的 Java 无法确定版本
+    if JavaMode::Manual == java.mode && JavaType::Custom == java.edition {
+        return vec![if check_java(&java.custom) {
+            Diagnosis::pass("Java", format!("Custom Java at {}", java.custom.display()))
+        } else {
+            Diagnosis::fail(
+                "Java",
+                format!("The custom Java {} cannot be used", java.custom.display()),
+                "Set `runtime.java.custom` to a working Java",
+            )
+        }];
+    }
+    let (edition, version) = match Lock::load().ok().and_then(|x| x.java) {
+        Some(locked) if JavaMode::Auto == java.mode => (locked.edition, locked.version),
+        None if JavaMode::Auto == java.mode => {
+            return vec![Diagnosis::warn(
+                "Java",
+                "The Java version has not been determined yet",
+                "Run `pacmine install` to prepare Java",
+            )];
+        }
+        _ => (java.edition.clone(), java.version),
+    };
+    let mut results = vec![];
+    if check_java(&runtime_path(&edition, version)) {
+        results.push(Diagnosis::pass(
+            "Java",
+            format!("{:?} {} is installed", edition, version),
+        ));
+    } else {
+        results.push(Diagnosis::fail(
+            "Java",
+            format!("{:?} {} is not installed or damaged", edition, version),
+            "Run `pacmine install` to install Java",
+        ));
+    }
+    // 模组服务端的启动器不能反映游戏需要的 Java 版本
+    let launcher = matches!(
+        config.project.server_type,
+        ServerType::Fabric | ServerType::Quilt
+    );
+    if provider_for(config).launch() == LaunchSpec::Jar
+        && !launcher
+        && let Ok(jar) = analyze_jar(&config.project.execute)
+    {
+        let required = jar.java_version as usize;
+        results.push(if required > version {
+            Diagnosis::fail(
+                "Java Version",
+                format!(
+                    "The server requires Java {}, but Java {} is used",
+                    required, version
+                ),
+                format!(
+                    "Set `runtime.java.version` to {} or use the auto mode",
+                    required
+                ),
+            )
+        } else {
+            Diagnosis::pass(
+                "Java Version",
+                format!("The server requires Java {}", required),
+            )
+        });
+    }
+    results
+}
+
+/// 检查 EULA，启动时会自动同意
+fn check_eula(config: &Config) -> Diagnosis {
+    if config.project.server_type == ServerType::BDS || config.project.server_type.is_proxy() {
+        return Diagnosis::pass("EULA", "Not required");
+    }
+    let accepted = fs::read_to_string("eula.txt")
+        .is_ok_and(|x| x.lines().any(|line| line.trim() == "eula=true"));
+    if accepted {
+        Diagnosis::pass("EULA", "Accepted")
+    } else {
+        Diagnosis::warn(
+            "EULA",
+            "The EULA has not been accepted",
+            "It will be accepted at the next start, see https://aka.ms/MinecraftEULA",
+        )
+    }
+}
+
+/// 检查服务端端口是否可用
+fn check_port(config: &Config) -> Diagnosis {
+    let bedrock = config.project.server_type == ServerType::BDS;
+    let default = if bedrock {
+        19132
+    } else if config.project.server_type.is_proxy() {
+        25577
+    } else {
+        25565
+    };
+    // 配置文件中的值会在启动时写入
+    let port = config
+        .properties
+        .get("server-port")
+        .and_then(|x| property_value(x).ok())
+        .or(ServerProperties::load(Path::new(PROPERTIES_FILE), bedrock)
+            .ok()
+            .and_then(|x| x.get("server-port").map(|x| x.to_string())))
+        .and_then(|x| x.parse::<u16>().ok())
+        .unwrap_or(default);
+    let available = if bedrock {
+        UdpSocket::bind(("0.0.0.0", port)).is_ok()
+    } else {
+        TcpListener::bind(("0.0.0.0", port)).is_ok()
+    };
+    if available {
+        Diagnosis::pass("Port", format!("{} is available", port))
+    } else {
+        Diagnosis::warn(
+            "Port",
+            format!("{} is in use", port),
+            "Stop the program using it, or change `server-port` in the [properties] section",
+        )
+    }
+}
+
+/// 检查备份仓库
+fn check_backup(config: &Config) -> Vec<Diagnosis> {
+    if !config.backup.enable {
+        return vec![];
+    }
+    let (world, other) = config.backup.targets();
+    [("world", world), ("other", other)]
+        .into_iter()
+        .filter_map(|(name, repo)| Some((name, repo?)))
+        .flat_map(|(name, repo)| {
+            [repo.backend]
+                .into_iter()
+                .chain(repo.copy)
+                .map(move |backend| check_repository(name, &backend))
+        })
+        .collect()
+}
+
+/// 检查单个备份仓库
+fn check_repository(name: &str, backend: &BackupBackend) -> Diagnosis {
+    let location = &backend.repository;
+    if backend.local_path().is_some_and(|path| !path.exists()) {
+        return Diagnosis::pass(
+            "Backup",
+            format!(
+                "The {} repository {} will be created at the first backup",
+                name, location
+            ),
+        );
+    }
+    match backup_check_repo(backend) {
+        Ok(_) => Diagnosis::pass(
+            "Backup",
+            format!("The {} repository {} is healthy", name, location),
+        ),
+        Err(e) => Diagnosis::fail(
+            "Backup",
+            format!("The {} repository {} is unavailable: {}", name, location, e),
+            format!("Check the backend options or repair {}", location),
+        ),
+    }
+}
+
+/// 检查项目所在磁盘的可用空间
+#[cfg(target_family = "unix")]
+fn check_disk() -> Option<Diagnosis> {
+    let stat = nix::sys::statvfs::statvfs(".").ok()?;
+    let free = stat.blocks_available() as u64 * stat.fragment_size() as u64 / 1024 / 1024;
+    Some(if free < MIN_FREE_DISK {
+        Diagnosis::warn(
+            "Disk",
+            format!("Only {} MB of free space", free),
+            "Free up disk space, the server and backups may fail to write",
+        )
+    } else {
+        Diagnosis::pass("Disk", format!("{} MB of free space", free))
+    })
+}
+
+/// 检查主机内存是否满足 `xmx`
+#[cfg(target_os = "linux")]
+fn check_memory(config: &Config) -> Option<Diagnosis> {
+    let total = nix::sys::sysinfo::sysinfo().ok()?.ram_total() / 1024 / 1024;
+    let xmx = config.runtime.java.xmx as u64;
+    Some(if xmx > total {
+        Diagnosis::fail(
+            "Memory",
+            format!("Xmx is {} MB, but the host only has {} MB", xmx, total),
+            "Reduce `runtime.java.xmx`",
+        )
+    } else {
+        Diagnosis::pass("Memory", format!("{} MB in total", total))
+    })
+}
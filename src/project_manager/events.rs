@@ -0,0 +1,397 @@
+use crate::project_manager::tools::ServerType;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// 最多记录的尚未进入游戏的玩家数量，超出时清空
+const PENDING_PLAYERS: usize = 256;
+
+/// 从服务端输出中解析出的事件
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// 玩家进入游戏
+    Join {
+        player: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        uuid: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        xuid: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ip: Option<String>,
+    },
+    /// 玩家离开游戏
+    Leave {
+        player: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// 玩家发送的聊天消息
+    Chat { player: String, message: String },
+    /// 玩家死亡，`message` 为完整的死亡消息
+    Death { player: String, message: String },
+    /// 玩家执行的命令
+    Command { player: String, command: String },
+    /// 服务端无法跟上 tick
+    Lag { behind_ms: u64, ticks: u64 },
+    /// 服务端输出的异常
+    Exception { message: String },
+}
+
+/// 玩家进入游戏前输出的信息
+#[derive(Default)]
+struct Pending {
+    uuid: Option<String>,
+    ip: Option<String>,
+    reason: Option<String>,
+}
+
+/// 按服务端类型的日志格式将输出的每一行解析为事件
+pub struct LogParser {
+    /// 服务端类型
+    server_type: ServerType,
+    /// Java 版服务端在玩家进入或离开前输出的 UUID、IP 和断开原因
+    pending: HashMap<String, Pending>,
+    /// 在线的玩家，只有在线玩家开头的消息才可能是死亡消息
+    online: HashSet<String>,
+}
+
+lazy_static! {
+    // Vanilla: "[12:00:00] [Server thread/INFO]: ", Paper: "[12:00:00 INFO]: "
+    static ref JAVA_PREFIX_RE: Regex = Regex::new(r"^\[[^\]]+\](?: \[[^\]]+\])?: ").unwrap();
+    // "[2025-01-01 12:00:00:000 INFO] "，早期版本为 "[INFO] "
+    static ref BDS_PREFIX_RE: Regex = Regex::new(r"^\[[^\]]+\] ").unwrap();
+    // Waterfall 和 BungeeCord: "12:00:00 [INFO] "
+    static ref PROXY_PREFIX_RE: Regex = Regex::new(r"^\d{2}:\d{2}:\d{2} \[[^\]]+\] ").unwrap();
+    static ref UUID_RE: Regex = Regex::new(r"^UUID of player (\S+) is ([0-9a-fA-F-]{36})$").unwrap();
+    static ref LOGIN_RE: Regex = Regex::new(r"^(\S+)\[/(.+)\] logged in with entity id").unwrap();
+    static ref JOIN_RE: Regex = Regex::new(r"^(\S+) joined the game$").unwrap();
+    static ref LOST_RE: Regex = Regex::new(r"^(\S+) lost connection: (.+)$").unwrap();
+    static ref LEAVE_RE: Regex = Regex::new(r"^(\S+) left the game$").unwrap();
+    static ref CHAT_RE: Regex = Regex::new(r"^(?:\[Not Secure\] )?<(\S+)> (.*)$").unwrap();
+    static ref COMMAND_RE: Regex = Regex::new(r"^(\S+) issued server command: (.+)$").unwrap();
+    static ref LAG_RE: Regex =
+        Regex::new(r"^Can't keep up! .*Running (\d+)ms or (\d+) ticks behind").unwrap();
+    static ref EXCEPTION_RE: Regex = Regex::new(
+        r"^(?:Exception in thread .+|(?:[a-zA-Z_$][\w$]*\.)+[\w$]*(?:Exception|Error)(?:: .*)?)$"
+    )
+    .unwrap();
+    // 原版的死亡消息均以玩家名开头，后接以下内容之一
+    static ref DEATH_RE: Regex = Regex::new(concat!(
+        r"^([.\w]{1,16}) (?:",
+        r"was (?:slain|shot|killed|blown up|pummeled|fireballed|pricked|squashed|squished|impaled|",
+        r"stung|poked|skewered|struck by lightning|burnt|frozen|obliterated|doomed|knocked|roasted|stomped)|",
+        r"drowned|died|blew up|burned to death|fell |hit the ground too hard|tried to swim in lava|",
+        r"went up in flames|went off with a bang|walked into|starved to death|suffocated in a wall|",
+        r"froze to death|experienced kinetic energy|withered away|discovered the floor was lava|",
+        r"left the confines of this world|didn't want to live)"
+    ))
+    .unwrap();
+    static ref BDS_CONNECT_RE: Regex =
+        Regex::new(r"^Player connected: (.+), xuid: (\d*)").unwrap();
+    static ref BDS_DISCONNECT_RE: Regex =
+        Regex::new(r"^Player disconnected: (.+), xuid: ").unwrap();
+}
+
+/// 去掉日志行的时间和级别前缀，返回消息部分，没有前缀的行原样返回
+pub fn log_message<'a>(server_type: &ServerType, line: &'a str) -> &'a str {
+    let prefix = match server_type {
+        ServerType::BDS => &*BDS_PREFIX_RE,
+        ServerType::Waterfall | ServerType::BungeeCord => &*PROXY_PREFIX_RE,
+        _ => &*JAVA_PREFIX_RE,
+    };
+    prefix.find(line).map_or(line, |x| &line[x.end()..])
+}
+
+impl LogParser {
+    /// 创建对应服务端类型的解析器
+    pub fn new(server_type: ServerType) -> Self {
+        LogParser {
+            server_type,
+            pending: HashMap::new(),
+            online: HashSet::new(),
+        }
+    }
+
+    /// 服务端重新启动时清空未完成的记录
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.online.clear();
+    }
+
+    /// 解析一行输出，不是事件时返回 None
+    pub fn parse(&mut self, line: &str) -> Option<Event> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if self.server_type == ServerType::BDS {
+            self.parse_bedrock(line)
+        } else {
+            self.parse_java(line)
+        }
+    }
+
+    /// Java 版服务端，包括 Vanilla、Paper 及其分支和模组服务端
+    fn parse_java(&mut self, line: &str) -> Option<Event> {
+        // 堆栈跟踪等没有前缀的行按原样匹配
+        let message = log_message(&self.server_type, line);
+
+        if let Some(caps) = CHAT_RE.captures(message) {
+            return Some(Event::Chat {
+                player: caps[1].to_string(),
+                message: caps[2].to_string(),
+            });
+        }
+        if let Some(caps) = UUID_RE.captures(message) {
+            self.pending_mut(&caps[1]).uuid = Some(caps[2].to_lowercase());
+            return None;
+        }
+        if let Some(caps) = LOGIN_RE.captures(message) {
+            // 去掉端口
+            let address = &caps[2];
+            let ip = address.rsplit_once(':').map_or(address, |(ip, _)| ip);
+            self.pending_mut(&caps[1]).ip = Some(ip.to_string());
+            return None;
+        }
+        if let Some(caps) = JOIN_RE.captures(message) {
+            let pending = self.pending.remove(&caps[1]).unwrap_or_default();
+            self.online.insert(caps[1].to_string());
+            return Some(Event::Join {
+                player: caps[1].to_string(),
+                uuid: pending.uuid,
+                xuid: None,
+                ip: pending.ip,
+            });
+        }
+        if let Some(caps) = LOST_RE.captures(message) {
+            self.pending_mut(&caps[1]).reason = Some(caps[2].to_string());
+            return None;
+        }
+        if let Some(caps) = LEAVE_RE.captures(message) {
+            let pending = self.pending.remove(&caps[1]).unwrap_or_default();
+            self.online.remove(&caps[1]);
+            return Some(Event::Leave {
+                player: caps[1].to_string(),
+                reason: pending.reason,
+            });
+        }
+        if let Some(caps) = COMMAND_RE.captures(message) {
+            return Some(Event::Command {
+                player: caps[1].to_string(),
+                command: caps[2].to_string(),
+            });
+        }
+        if let Some(caps) = LAG_RE.captures(message) {
+            return Some(Event::Lag {
+                behind_ms: caps[1].parse().ok()?,
+                ticks: caps[2].parse().ok()?,
+            });
+        }
+        if EXCEPTION_RE.is_match(message) {
+            return Some(Event::Exception {
+                message: message.to_string(),
+            });
+        }
+        // 死亡消息没有固定的格式，插件或其他输出可能以相同的内容开头，只接受在线玩家
+        if let Some(caps) = DEATH_RE.captures(message)
+            && self.online.contains(&caps[1])
+        {
+            return Some(Event::Death {
+                player: caps[1].to_string(),
+                message: message.to_string(),
+            });
+        }
+        None
+    }
+
+    /// BDS，仅输出玩家的连接和断开
+    fn parse_bedrock(&mut self, line: &str) -> Option<Event> {
+        let message = log_message(&self.server_type, line);
+        let xuid = |x: &str| (!x.is_empty()).then(|| x.to_string());
+
+        if let Some(caps) = BDS_CONNECT_RE.captures(message) {
+            return Some(Event::Join {
+                player: caps[1].to_string(),
+                uuid: None,
+                xuid: xuid(&caps[2]),
+                ip: None,
+            });
+        }
+        if let Some(caps) = BDS_DISCONNECT_RE.captures(message) {
+            return Some(Event::Leave {
+                player: caps[1].to_string(),
+                reason: None,
+            });
+        }
+        None
+    }
+
+    /// 获取玩家进入游戏前的记录
+    fn pending_mut(&mut self, player: &str) -> &mut Pending {
+        // 登录失败的玩家不会进入游戏，避免无限增长
+        if self.pending.len() >= PENDING_PLAYERS && !self.pending.contains_key(player) {
+            self.pending.clear();
+        }
+        self.pending.entry(player.to_string()).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vanilla() {
+        let mut parser = LogParser::new(ServerType::Vanilla);
+        let lines = [
+            "[12:00:00] [User Authenticator #1/INFO]: UUID of player Steve is 069A79F4-44E9-4726-A5BE-FCA90E38AAF5\n",
+            "[12:00:00] [Server thread/INFO]: Steve[/127.0.0.1:54321] logged in with entity id 123 at (0.5, 64.0, 0.5)\n",
+            "[12:00:00] [Server thread/INFO]: Steve joined the game\n",
+            "[12:00:01] [Server thread/INFO]: <Steve> hello, Alex joined the game\n",
+            "[12:00:02] [Server thread/INFO]: Steve was slain by Zombie\n",
+            "[12:00:03] [Server thread/WARN]: Can't keep up! Is the server overloaded? Running 5000ms or 100 ticks behind\n",
+            "[12:00:04] [Server thread/INFO]: Steve lost connection: Disconnected\n",
+            "[12:00:04] [Server thread/INFO]: Steve left the game\n",
+            "[12:00:05] [Server thread/INFO]: Done (3.456s)! For help, type \"help\"\n",
+        ];
+        let events: Vec<_> = lines.iter().filter_map(|x| parser.parse(x)).collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::Join {
+                    player: "Steve".to_string(),
+                    uuid: Some("069a79f4-44e9-4726-a5be-fca90e38aaf5".to_string()),
+                    xuid: None,
+                    ip: Some("127.0.0.1".to_string()),
+                },
+                Event::Chat {
+                    player: "Steve".to_string(),
+                    message: "hello, Alex joined the game".to_string(),
+                },
+                Event::Death {
+                    player: "Steve".to_string(),
+                    message: "Steve was slain by Zombie".to_string(),
+                },
+                Event::Lag {
+                    behind_ms: 5000,
+                    ticks: 100,
+                },
+                Event::Leave {
+                    player: "Steve".to_string(),
+                    reason: Some("Disconnected".to_string()),
+                },
+            ]
+        );
+        assert!(parser.pending.is_empty());
+    }
+
+    #[test]
+    fn test_parse_paper() {
+        let mut parser = LogParser::new(ServerType::Paper);
+        assert_eq!(
+            parser.parse("[12:00:00 INFO]: [Not Secure] <Alex> hi\n"),
+            Some(Event::Chat {
+                player: "Alex".to_string(),
+                message: "hi".to_string(),
+            })
+        );
+        assert_eq!(
+            parser.parse("[12:00:01 INFO]: Alex issued server command: /gamemode creative\n"),
+            Some(Event::Command {
+                player: "Alex".to_string(),
+                command: "/gamemode creative".to_string(),
+            })
+        );
+        assert_eq!(
+            parser.parse("java.lang.NullPointerException: Cannot invoke \"Object.toString()\"\n"),
+            Some(Event::Exception {
+                message: "java.lang.NullPointerException: Cannot invoke \"Object.toString()\""
+                    .to_string(),
+            })
+        );
+        assert_eq!(
+            parser.parse("\tat org.bukkit.plugin.java.JavaPlugin.onEnable(JavaPlugin.java:1)\n"),
+            None
+        );
+        // 不在线的玩家不会死亡
+        assert_eq!(
+            parser.parse("[12:00:02 INFO]: Alex fell from a high place\n"),
+            None
+        );
+        assert!(
+            parser
+                .parse("[12:00:02 INFO]: Alex joined the game\n")
+                .is_some()
+        );
+        assert_eq!(
+            parser.parse("[12:00:03 INFO]: Alex fell from a high place\n"),
+            Some(Event::Death {
+                player: "Alex".to_string(),
+                message: "Alex fell from a high place".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_death_in_chat() {
+        let mut parser = LogParser::new(ServerType::Paper);
+        assert!(
+            parser
+                .parse("[12:00:00 INFO]: Steve joined the game\n")
+                .is_some()
+        );
+        assert_eq!(
+            parser.parse("[12:00:01 INFO]: <Alex> Steve fell from a high place\n"),
+            Some(Event::Chat {
+                player: "Alex".to_string(),
+                message: "Steve fell from a high place".to_string(),
+            })
+        );
+        assert_eq!(
+            parser.parse("[12:00:02 INFO]: [Server] Steve drowned\n"),
+            None
+        );
+        assert_eq!(
+            parser.parse("[12:00:03 INFO]: [Essentials] Steve fell asleep\n"),
+            None
+        );
+        assert_eq!(
+            parser.parse("[12:00:04 INFO]: Alex fell from a high place\n"),
+            None
+        );
+        assert!(
+            parser
+                .parse("[12:00:05 INFO]: Steve left the game\n")
+                .is_some()
+        );
+        assert_eq!(parser.parse("[12:00:06 INFO]: Steve died\n"), None);
+    }
+
+    #[test]
+    fn test_parse_bedrock() {
+        let mut parser = LogParser::new(ServerType::BDS);
+        assert_eq!(
+            parser.parse(
+                "[2025-01-01 12:00:00:000 INFO] Player connected: Steve Two, xuid: 2535412345678901\n"
+            ),
+            Some(Event::Join {
+                player: "Steve Two".to_string(),
+                uuid: None,
+                xuid: Some("2535412345678901".to_string()),
+                ip: None,
+            })
+        );
+        assert_eq!(
+            parser.parse(
+                "[2025-01-01 12:00:09:000 INFO] Player disconnected: Steve Two, xuid: 2535412345678901, pfid: 1a2b3c\n"
+            ),
+            Some(Event::Leave {
+                player: "Steve Two".to_string(),
+                reason: None,
+            })
+        );
+        assert_eq!(
+            parser.parse("[2025-01-01 12:00:00:000 INFO] Server started.\n"),
+            None
+        );
+    }
+}
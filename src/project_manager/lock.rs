@@ -0,0 +1,196 @@
+use crate::project_manager::LOCK_FILE;
+use crate::project_manager::config::{Config, JavaType};
+use crate::project_manager::tools::{CoreArtifact, FileDownloadResult, ServerType, hash_file};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// 锁文件头部的说明
+const LOCK_HEADER: &str = "# This file is automatically generated by PacMine.\n# It is not intended for manual editing.\n\n";
+
+/// 锁文件，记录实际安装的服务端、运行环境和插件
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Lock {
+    /// 服务端核心
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) core: Option<LockedCore>,
+    /// Java 运行环境
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) java: Option<LockedJava>,
+    /// 受管理的插件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) plugin: Vec<LockedPlugin>,
+}
+
+/// 锁定的服务端核心
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LockedCore {
+    /// 服务端类型
+    pub(crate) server_type: ServerType,
+    /// 游戏版本
+    pub(crate) version: String,
+    /// 构建号，仅 Paper 类服务端存在
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) build: Option<usize>,
+    /// 模组加载器版本，仅模组服务端存在
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) loader: Option<String>,
+    /// 下载链接，非 PacMine 安装的服务端不存在
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) url: Option<String>,
+    /// 文件 SHA256，基岩版为下载的压缩包，使用安装器的服务端为生成的启动文件
+    pub(crate) sha256: String,
+    /// 文件 SHA1，基岩版为下载的压缩包，使用安装器的服务端为生成的启动文件
+    pub(crate) sha1: String,
+}
+
+/// 锁定的 Java 运行环境
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct LockedJava {
+    /// Java 环境类型
+    pub(crate) edition: JavaType,
+    /// Java 版本
+    pub(crate) version: usize,
+}
+
+/// 锁定的插件
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LockedPlugin {
+    /// 插件名称
+    pub(crate) name: String,
+    /// 已安装的版本
+    pub(crate) version: String,
+    /// 下载链接
+    pub(crate) url: String,
+    /// 插件目录中的文件名
+    pub(crate) file: String,
+    /// 文件 SHA256
+    pub(crate) sha256: String,
+    /// 文件 SHA1
+    pub(crate) sha1: String,
+}
+
+impl Lock {
+    /// 读取锁文件，不存在时返回空的锁
+    pub fn load() -> Result<Self, Error> {
+        if !Path::new(LOCK_FILE).is_file() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(LOCK_FILE)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// 写入锁文件
+    pub fn save(&self) -> Result<(), Error> {
+        let content = toml::to_string_pretty(self)?;
+        fs::write(LOCK_FILE, format!("{}{}", LOCK_HEADER, content))?;
+        Ok(())
+    }
+
+    /// 获取与配置文件一致的服务端核心记录
+    pub fn core_for(&self, config: &Config) -> Option<&LockedCore> {
+        self.core.as_ref().filter(|x| {
+            x.server_type == config.project.server_type
+                && x.version == config.project.version
+                && config
+                    .project
+                    .loader_version
+                    .as_ref()
+                    .is_none_or(|loader| x.loader.as_ref() == Some(loader))
+                && config
+                    .project
+                    .build
+                    .is_none_or(|build| x.build == Some(build))
+        })
+    }
+
+    /// 按名称查找插件
+    pub fn plugin(&self, name: &str) -> Option<&LockedPlugin> {
+        self.plugin.iter().find(|x| x.name == name)
+    }
+
+    /// 插入或替换插件记录
+    pub fn upsert_plugin(&mut self, locked: LockedPlugin) {
+        self.plugin.retain(|x| x.name != locked.name);
+        self.plugin.push(locked);
+    }
+}
+
+impl LockedCore {
+    /// 根据安装的文件创建记录
+    pub fn new(artifact: &CoreArtifact, file: &FileDownloadResult) -> Self {
+        LockedCore {
+            server_type: artifact.server_type.clone(),
+            version: artifact.version.clone(),
+            build: artifact.build,
+            loader: artifact.loader.clone(),
+            url: Some(file.url.clone()),
+            sha256: file.sha256.clone(),
+            sha1: file.sha1.clone(),
+        }
+    }
+
+    /// 为非 PacMine 安装的服务端创建记录，仅包含哈希值
+    pub fn from_file(config: &Config, path: &Path) -> Result<Self, Error> {
+        let (sha256, sha1) = hash_file(path)?;
+        Ok(LockedCore {
+            server_type: config.project.server_type.clone(),
+            version: config.project.version.clone(),
+            build: None,
+            loader: config.project.loader_version.clone(),
+            url: None,
+            sha256,
+            sha1,
+        })
+    }
+
+    /// 转换为可安装的服务端，没有下载链接时返回 None
+    pub fn to_artifact(&self) -> Option<CoreArtifact> {
+        Some(CoreArtifact {
+            server_type: self.server_type.clone(),
+            version: self.version.clone(),
+            build: self.build,
+            loader: self.loader.clone(),
+            url: self.url.clone()?,
+            md5: None,
+            sha1: Some(self.sha1.clone()),
+            sha256: Some(self.sha256.clone()),
+            changelog: vec![],
+        })
+    }
+
+    /// 检查文件是否与记录一致
+    pub fn verify(&self, path: &Path) -> bool {
+        hash_file(path).is_ok_and(|(sha256, _)| sha256 == self.sha256)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_serialization() {
+        let lock = Lock {
+            core: Some(LockedCore {
+                server_type: ServerType::Paper,
+                version: "1.21.1".to_string(),
+                build: Some(132),
+                loader: None,
+                url: Some("https://example.com/paper-1.21.1-132.jar".to_string()),
+                sha256: "0".repeat(64),
+                sha1: "0".repeat(40),
+            }),
+            java: Some(LockedJava {
+                edition: JavaType::OpenJDK,
+                version: 21,
+            }),
+            plugin: vec![],
+        };
+        let toml_str = toml::to_string_pretty(&lock).unwrap();
+        println!("{}", toml_str);
+        let lock: Lock = toml::from_str(&format!("{}{}", LOCK_HEADER, toml_str)).unwrap();
+        assert_eq!(lock.core.unwrap().build, Some(132));
+    }
+}
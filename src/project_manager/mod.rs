@@ -1,16 +1,35 @@
+mod backup;
 pub(crate) mod config;
+pub(crate) mod console;
 pub(crate) mod create;
+mod doctor;
+pub(crate) mod events;
 mod info;
+pub(crate) mod lock;
+mod properties;
 pub(crate) mod run;
+pub(crate) mod state;
 pub mod tools;
+mod update;
+mod upgrade;
 
+pub use backup::{
+    BackupRepo, NewPassword, check_backups, create_backup, diff_backups, forget_backups,
+    list_backups, prune_backups, rekey_backups, restore_backup,
+};
 pub use config::Config;
-pub use create::create_project;
+pub use create::{CreateOptions, create_project};
+pub use doctor::doctor;
 pub use info::{get_info, print_info};
+pub use properties::{apply_properties, diff_properties};
 pub use run::{pre_run, start_server};
+pub use update::update_plugins;
+pub use upgrade::{rollback, upgrade_server};
 
 /// 配置文件
 pub const CONFIG_FILE: &str = "PacMine.toml";
+/// 锁文件
+pub const LOCK_FILE: &str = "PacMine.lock";
 /// 工作目录
 pub const WORK_DIR: &str = ".pacmine";
 /// 缓存目录
@@ -21,16 +40,49 @@ pub const BACKUP_DIR: &str = ".pacmine/backup";
 pub const RUNTIME_DIR: &str = ".pacmine/runtime";
 /// 日志目录
 pub const LOG_DIR: &str = ".pacmine/log";
+/// 插件目录
+pub const PLUGIN_DIR: &str = "plugins";
+/// 服务端配置文件
+pub const PROPERTIES_FILE: &str = "server.properties";
+/// 升级前的服务端和配置
+pub const ROLLBACK_DIR: &str = ".pacmine/rollback";
+/// 当前安装的 Bedrock Edition 服务端压缩包，升级时保存到回滚目录
+pub const BDS_ARCHIVE: &str = ".pacmine/bedrock-server.zip";
+/// 项目模板目录，位于家目录中
+pub const TEMPLATE_DIR: &str = ".pacmine/templates";
 
-// Paper 类服务端 V2 版本 API 的 URL
-/// Paper API
-const PAPER_PROJECT_API: &str = "https://api.papermc.io/v2/projects/paper";
-/// Folia API
-const FOLIA_PROJECT_API: &str = "https://api.papermc.io/v2/projects/folia";
-/// Leaves API
+/// PaperMC Fill API，用于 Paper、Folia、Velocity 和 Waterfall
+const FILL_API: &str = "https://fill.papermc.io/v3/projects";
+
+/// Leaves API，与 PaperMC 的 V2 API 相同
 const LEAVES_PROJECT_API: &str = "https://api.leavesmc.org/v2/projects/leaves";
 /// Purpur API
-const PURPUR_PROJECT_API: &str = "https://api.purpurmc.org/v2/purpur/";
+const PURPUR_PROJECT_API: &str = "https://api.purpurmc.org/v2/purpur";
+
+/// BungeeCord 的 Jenkins 任务
+const BUNGEECORD_JOB: &str = "https://ci.md-5.net/job/BungeeCord";
+
+/// Fabric Meta API
+const FABRIC_META_API: &str = "https://meta.fabricmc.net/v2";
+/// Quilt Meta API
+const QUILT_META_API: &str = "https://meta.quiltmc.org/v3";
+
+/// Forge 推荐版本列表
+const FORGE_PROMOTIONS_API: &str =
+    "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+/// Forge Maven 仓库
+const FORGE_MAVEN: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
+/// NeoForge 版本列表 API
+const NEOFORGE_VERSIONS_API: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
+/// NeoForge Maven 仓库
+const NEOFORGE_MAVEN: &str = "https://maven.neoforged.net/releases/net/neoforged/neoforge";
+
+/// Bedrock Edition 服务端下载链接 API
+const BDS_DOWNLOAD_API: &str =
+    "https://net-secondary.web.minecraft-services.net/api/v1.0/download/links";
+/// Bedrock Edition 服务端下载地址
+const BDS_DOWNLOAD_URL: &str = "https://www.minecraft.net/bedrockdedicatedserver";
 
 /// 默认下载线程
 const DEFAULT_DOWNLOAD_THREAD: usize = 5;
@@ -38,8 +90,25 @@ const DEFAULT_DOWNLOAD_THREAD: usize = 5;
 /// 下载每个分块最大重试次数
 const MAX_RETRIES: usize = 3;
 
-/// 默认的备份密码，无意义，所以用空字符串
+/// 未设置 `[backup.password]` 时使用的空密码，兼容早期创建的仓库
 const PASSWORD: &str = "";
 
+/// 备份运行中的服务端时等待存档写入完成的最长时间，单位秒
+const SAVE_TIMEOUT: u64 = 60;
+
+/// 控制台保留的服务端最近输出的行数，服务端重启时记录到日志中
+const HISTORY_LINES: usize = 20;
+
+/// 等待服务端启动就绪的最长时间，单位秒
+const READY_TIMEOUT: u64 = 300;
+
+/// Modrinth API
+const MODRINTH_API: &str = "https://api.modrinth.com/v2";
+/// Hangar API
+const HANGAR_API: &str = "https://hangar.papermc.io/api/v1";
+
+/// 请求第三方 API 时使用的 User-Agent
+const USER_AGENT: &str = concat!("PacMine/", env!("CARGO_PKG_VERSION"));
+
 /// MOJANG 下载 API 的 URL
 const VERSION_API_URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest_v2.json";
@@ -0,0 +1,85 @@
+use crate::project_manager::tools::{
+    ServerProperties, ServerType, property_value, validate_property,
+};
+use crate::project_manager::{Config, PROPERTIES_FILE};
+use anyhow::Error;
+use colored::Colorize;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// 检查配置文件中的配置项，返回转换后的值
+pub fn checked_properties(config: &Config) -> Result<Vec<(String, String)>, Error> {
+    if !config.properties.is_empty() && config.project.server_type.is_proxy() {
+        return Err(Error::msg(format!(
+            "The proxy server {:?} does not use {}",
+            config.project.server_type, PROPERTIES_FILE
+        )));
+    }
+    let bedrock = config.project.server_type == ServerType::BDS;
+    config
+        .properties
+        .iter()
+        .map(|(key, value)| {
+            let value = property_value(value)?;
+            validate_property(bedrock, key, &value)?;
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+/// 将配置文件中的配置项写入 `server.properties`
+pub fn apply_properties(config: &Config) -> Result<(), Error> {
+    let properties = checked_properties(config)?;
+    if properties.is_empty() {
+        return Ok(());
+    }
+    let path = Path::new(PROPERTIES_FILE);
+    let mut file = ServerProperties::load(path, config.project.server_type == ServerType::BDS)?;
+    let mut changed = false;
+    for (key, value) in &properties {
+        if file.get(key) != Some(value.as_str()) {
+            debug!("Set {} to {:?}", key, value);
+            file.set(key, value);
+            changed = true;
+        }
+    }
+    if changed {
+        file.save(path)?;
+    }
+    Ok(())
+}
+
+/// 输出 `server.properties` 与配置文件不一致的配置项
+pub fn diff_properties(config: &Config) -> Result<(), Error> {
+    let properties = checked_properties(config)?;
+    let file = ServerProperties::load(
+        Path::new(PROPERTIES_FILE),
+        config.project.server_type == ServerType::BDS,
+    )?;
+    let mut drift = false;
+    for (key, value) in &properties {
+        match file.get(key) {
+            Some(current) if current == value => {}
+            Some(current) => {
+                println!(
+                    "  {} {} {} -> {}",
+                    "~".yellow(),
+                    key,
+                    current.yellow(),
+                    value.green()
+                );
+                drift = true;
+            }
+            None => {
+                println!("  {} {} {}", "+".green(), key, value.green());
+                drift = true;
+            }
+        }
+    }
+    if drift {
+        info!("Run `pacmine properties apply` or start the server to apply the changes");
+    } else {
+        info!("{} matches the configuration", PROPERTIES_FILE);
+    }
+    Ok(())
+}
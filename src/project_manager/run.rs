@@ -1,29 +1,66 @@
-use crate::project_manager::config::{JavaMode, JavaType};
-use crate::project_manager::tools::backup::{backup_check_repo, backup_init_repo, backup_new_snap};
+use crate::project_manager::config::{
+    BackupBackend, BackupRepository, JavaMode, JavaType, Retention, Schedule, ScheduleAction,
+};
+use crate::project_manager::console::{Console, Held};
+use crate::project_manager::events::Event;
+use crate::project_manager::lock::{Lock, LockedCore, LockedJava};
+use crate::project_manager::state::ServerState;
+use crate::project_manager::tools::backup::{
+    backup_apply_retention, backup_check_repo, backup_copy_snaps, backup_init_repo, backup_new_snap,
+};
 use crate::project_manager::tools::{
-    ServerType, VersionInfo, analyze_jar, check_java, get_mime_type, install_bds, install_je,
-    prepare_java,
+    LaunchSpec, ServerType, VersionManifest, analyze_jar, check_java, install_locked_plugin,
+    prepare_java, provider_for,
+};
+use crate::project_manager::{
+    CACHE_DIR, Config, LOCK_FILE, LOG_DIR, READY_TIMEOUT, WORK_DIR, apply_properties, get_info,
 };
-use crate::project_manager::{BACKUP_DIR, Config, LOG_DIR, WORK_DIR, get_info};
 use anyhow::Error;
 use chrono::{Local, Utc};
 use cron_tab::AsyncCron;
+use futures::FutureExt;
 use futures::future::join_all;
 use std::io::Cursor;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
+use std::time::Duration;
 use std::{env, fs};
 use tokio::fs::OpenOptions;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, stdin, stdout};
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::runtime::Runtime;
-use tokio::sync::{Mutex, Notify, mpsc};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Mutex, Notify, broadcast, mpsc};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tokio::{select, signal, spawn};
 use tracing::{debug, error, info, warn};
 
+/// Java 版服务端的启动参数，按照服务端来源的启动方式使用 jar 或参数文件启动
+fn java_args(config: &Config) -> Vec<String> {
+    let mut args = config.runtime.java.arguments.clone();
+    if config.runtime.java.xms != 0 {
+        args.push(format!("-Xms{}M", config.runtime.java.xms));
+    }
+    if config.runtime.java.xmx != 0 {
+        args.push(format!("-Xmx{}M", config.runtime.java.xmx));
+    }
+    match provider_for(config).launch() {
+        LaunchSpec::ArgFile => {
+            args.push(format!("@{}", config.project.execute.display()));
+            args.push("nogui".to_string());
+        }
+        _ => {
+            args.push("-jar".to_string());
+            args.push(config.project.execute.display().to_string());
+            args.push("-nogui".to_string());
+        }
+    }
+    args
+}
+
 /// 生成启动脚本
 pub fn generate_scripts() {
     let config = get_info().expect("Failed to get project info");
@@ -41,13 +78,14 @@ pub fn generate_scripts() {
         writeln!(file, "#!/bin/sh").unwrap();
         writeln!(file).unwrap();
 
-        if let ServerType::BDS = config.project.server_type {
-            // Bedrock Edition
+        if provider_for(&config).launch() == LaunchSpec::Native {
+            // Bedrock Edition 等直接运行的服务端
             writeln!(file, "export LD_LIBRARY_PATH=.").unwrap();
             writeln!(file).unwrap();
-            writeln!(file, "chmod +x {}", config.project.execute.display()).unwrap();
+            let execute = Path::new(".").join(&config.project.execute);
+            writeln!(file, "chmod +x {}", execute.display()).unwrap();
             writeln!(file).unwrap();
-            writeln!(file, "{}", config.project.execute.display()).unwrap();
+            writeln!(file, "{}", execute.display()).unwrap();
         } else {
             // Java Edition
             if let Ok(mut java_path) = config.runtime.java.to_binary() {
@@ -56,25 +94,7 @@ pub fn generate_scripts() {
                 writeln!(file).unwrap();
             }
 
-            let extra_arg = config.runtime.java.arguments.join(" ");
-
-            let mut mem_options = Vec::new();
-            if config.runtime.java.xms != 0 {
-                mem_options.push(format!("-Xms{}M", config.runtime.java.xms));
-            }
-            if config.runtime.java.xmx != 0 {
-                mem_options.push(format!("-Xmx{}M", config.runtime.java.xmx));
-            }
-            let mem_arg = mem_options.join(" ");
-
-            writeln!(
-                file,
-                "java {} {} -jar {} -nogui",
-                extra_arg,
-                mem_arg,
-                config.project.execute.display()
-            )
-            .unwrap();
+            writeln!(file, "java {}", java_args(&config).join(" ")).unwrap();
         }
         file.flush().unwrap();
         drop(file);
@@ -93,8 +113,8 @@ pub fn generate_scripts() {
         writeln!(file, "@echo off").unwrap();
         writeln!(file).unwrap();
 
-        if let ServerType::BDS = config.project.server_type {
-            // Bedrock Edition
+        if provider_for(&config).launch() == LaunchSpec::Native {
+            // Bedrock Edition 等直接运行的服务端
             writeln!(file, "{}", config.project.execute.display()).unwrap();
         } else {
             if let Ok(mut java_path) = config.runtime.java.to_binary() {
@@ -103,25 +123,7 @@ pub fn generate_scripts() {
                 writeln!(file).unwrap();
             }
 
-            let extra_arg = config.runtime.java.arguments.join(" ");
-
-            let mut mem_options = Vec::new();
-            if config.runtime.java.xms != 0 {
-                mem_options.push(format!("-Xms{}M", config.runtime.java.xms));
-            }
-            if config.runtime.java.xmx != 0 {
-                mem_options.push(format!("-Xmx{}M", config.runtime.java.xmx));
-            }
-            let mem_arg = mem_options.join(" ");
-
-            writeln!(
-                file,
-                "java.exe {} {} -jar {} -nogui",
-                mem_arg,
-                extra_arg,
-                config.project.execute.display()
-            )
-            .unwrap();
+            writeln!(file, "java.exe {}", java_args(&config).join(" ")).unwrap();
         }
         file.flush().unwrap();
         drop(file);
@@ -140,6 +142,7 @@ pub fn start_server(config: Config) -> Result<(), Error> {
 
     let config = Arc::new(config);
     let stop_flag = Arc::new(Notify::new());
+    let console = Console::new(config.project.server_type.clone());
 
     // 以下开始异步🤯
     let rt = Runtime::new()?;
@@ -147,12 +150,24 @@ pub fn start_server(config: Config) -> Result<(), Error> {
         let mut handles: Vec<JoinHandle<Result<(), Error>>> = vec![];
 
         // 启动 backup 线程
-        handles.push(spawn(backup_thread(Arc::clone(&config), stop_flag.clone())));
+        handles.push(spawn(backup_thread(
+            Arc::clone(&config),
+            stop_flag.clone(),
+            console.clone(),
+        )));
+
+        // 启动计划任务线程
+        handles.push(spawn(schedule_thread(
+            Arc::clone(&config),
+            stop_flag.clone(),
+            console.clone(),
+        )));
 
         // 启动服务器线程
         handles.push(spawn(server_thread_with_terminal(
             Arc::clone(&config),
             stop_flag.clone(),
+            console.clone(),
         )));
 
         // 停止信号
@@ -185,7 +200,11 @@ pub fn start_server(config: Config) -> Result<(), Error> {
 }
 
 /// 服务器线程(同步到终端)
-async fn server_thread_with_terminal(config: Arc<Config>, stop: Arc<Notify>) -> Result<(), Error> {
+async fn server_thread_with_terminal(
+    config: Arc<Config>,
+    stop: Arc<Notify>,
+    console: Arc<Console>,
+) -> Result<(), Error> {
     // channel：外层发送给 server_thread 的 stdin
     let (tx_in, rx_in) = mpsc::channel::<String>(100);
     // channel：server_thread 输出 stdout/stderr
@@ -195,7 +214,7 @@ async fn server_thread_with_terminal(config: Arc<Config>, stop: Arc<Notify>) ->
     let config_clone = config.clone();
     // spawn server_thread
     let server_handle = spawn(async move {
-        server_thread(rx_in, tx_out, stop_clone, config_clone)
+        server_thread(rx_in, tx_out, stop_clone, config_clone, console)
             .await
             .unwrap_or_else(|e| error!("Server thread error: {}", e));
     });
@@ -245,48 +264,153 @@ async fn server_thread_with_terminal(config: Arc<Config>, stop: Arc<Notify>) ->
     Ok(())
 }
 
-/// 服务端线程，仅同步到 mpsc 通道
+/// 服务端线程，仅同步到 mpsc 通道，服务端自行退出时按 `[runtime.restart]` 重启
 pub async fn server_thread(
     mut rx: mpsc::Receiver<String>, // 接收外部消息 -> 写入子进程 stdin
     tx: mpsc::Sender<String>,       // 发送子进程 stdout/stderr 给外部
     stop: Arc<Notify>,
     config: Arc<Config>,
+    console: Arc<Console>, // 供备份等任务向服务端发送命令
 ) -> Result<(), Error> {
     // 同意 https://aka.ms/MinecraftEULA
     accept_eula().await;
 
+    // rx -> stdin，重启后写入新的进程
+    let console_clone = console.clone();
+    let stop_clone = stop.clone();
+    let stdin_handle = spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            select! {
+                _ = stop_clone.notified() => break,
+                _ = async {
+                    let _ = console_clone.send(&msg).await;
+                } => {}
+            }
+        }
+        Ok::<(), Error>(())
+    });
+
+    // 提前注册停止信号，服务端与 PacMine 同时收到 Ctrl+C 而退出时不视为崩溃
+    let stopped = stop.notified();
+    tokio::pin!(stopped);
+    stopped.as_mut().enable();
+
+    // 将解析出的日志事件记录到 `{LOG_DIR}/events.log`
+    let events_handle = spawn(record_events(console.subscribe_events()));
+
+    let restart = config.runtime.restart.clone().unwrap_or_default();
+    let mut attempt = 0;
+    let result = loop {
+        let started = Instant::now();
+        let status = match run_server(&tx, &stop, &config, &console).await {
+            Ok(Exit::Exited(status)) => status,
+            // 由停止信号停止
+            Ok(Exit::Stopped) => {
+                console.set_state(ServerState::Stopped);
+                break Ok(());
+            }
+            // 计划任务请求的重启不计入重启次数
+            Ok(Exit::Restart) => {
+                if stopped.as_mut().now_or_never().is_some() {
+                    console.set_state(ServerState::Stopped);
+                    break Ok(());
+                }
+                info!("Restarting server...");
+                attempt = 0;
+                continue;
+            }
+            Err(e) => {
+                console.set_state(ServerState::Crashed);
+                break Err(e);
+            }
+        };
+        info!("Server exited: {:?}", status.code());
+        if stopped.as_mut().now_or_never().is_some() {
+            console.set_state(ServerState::Stopped);
+            break Ok(());
+        }
+        console.set_state(if status.success() {
+            ServerState::Stopped
+        } else {
+            ServerState::Crashed
+        });
+        // 运行超过检测窗口后重新计算重启次数
+        if started.elapsed() >= Duration::from_secs(restart.window) {
+            attempt = 0;
+        }
+        if !restart.should_restart(status.success()) {
+            stop.notify_waiters();
+            break Ok(());
+        }
+        if attempt >= restart.max_retries {
+            error!(
+                "The server has been restarted {} times but keeps crashing, give up restarting",
+                attempt
+            );
+            record_exit(status, "crash loop detected, not restarting", &console).await;
+            stop.notify_waiters();
+            break Ok(());
+        }
+        let delay = restart.delay(attempt);
+        attempt += 1;
+        let action = format!(
+            "restart {}/{} in {}s",
+            attempt,
+            restart.max_retries,
+            delay.as_secs()
+        );
+        warn!("Server crashed, {}", action);
+        record_exit(status, &action, &console).await;
+        select! {
+            _ = &mut stopped => break Ok(()),
+            _ = tokio::time::sleep(delay) => {}
+        }
+    };
+
+    stdin_handle.abort();
+    events_handle.abort();
+    drop(tx);
+
+    result
+}
+
+/// 服务端一次运行的结果
+enum Exit {
+    /// 由停止信号停止
+    Stopped,
+    /// 按请求停止，需要重新启动
+    Restart,
+    /// 服务端自行退出
+    Exited(ExitStatus),
+}
+
+/// 启动并运行一次服务端
+async fn run_server(
+    tx: &mpsc::Sender<String>,
+    stop: &Arc<Notify>,
+    config: &Config,
+    console: &Arc<Console>,
+) -> Result<Exit, Error> {
     // 启动子进程
-    let mut child = if let ServerType::BDS = config.as_ref().project.server_type {
+    let mut child = if provider_for(config).launch() == LaunchSpec::Native {
         info!("Server starting...");
-        Command::new(&config.as_ref().project.execute)
+        // 相对路径需要以 ./ 开头，否则会在 PATH 中查找
+        Command::new(Path::new(".").join(&config.project.execute))
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?
     } else {
         // Java
-        let mut mem_options = Vec::new();
-        if config.runtime.java.xms != 0 {
-            mem_options.push(format!("-Xms{}M", config.runtime.java.xms));
-        }
-        if config.runtime.java.xmx != 0 {
-            mem_options.push(format!("-Xmx{}M", config.runtime.java.xmx));
-        }
         info!("Server starting...");
         Command::new(config.runtime.java.to_binary()?)
-            .args(&config.runtime.java.arguments)
-            .args(mem_options)
-            .arg("-jar")
-            .arg(&config.project.execute)
-            .arg("-nogui")
+            .args(java_args(config))
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?
     };
 
-    let child_stdin = Arc::new(Mutex::new(child.stdin.take().unwrap()));
-
     // 日志文件
     let stdout_file = Arc::new(Mutex::new(
         OpenOptions::new()
@@ -314,18 +438,34 @@ pub async fn server_thread(
 
     let stdout = child.stdout.take().unwrap();
     let stderr = child.stderr.take().unwrap();
+    console.attach(child.stdin.take().unwrap()).await;
+    console.set_state(ServerState::Starting);
+
+    // 等待就绪，超时只记录警告
+    let console_clone = console.clone();
+    let ready_handle = spawn(async move {
+        if !console_clone
+            .wait_ready(Duration::from_secs(READY_TIMEOUT))
+            .await
+            && console_clone.status().state == ServerState::Starting
+        {
+            warn!("The server is not ready after {}s", READY_TIMEOUT);
+        }
+    });
 
     // stdout -> tx + log
     let tx_stdout = tx.clone();
     let stdout_file_clone = stdout_file.clone();
     let stop_clone = stop.clone();
-    let stdout_handle = spawn(async move {
+    let console_clone = console.clone();
+    let mut stdout_handle = spawn(async move {
         let mut reader = BufReader::new(stdout);
         let mut line = String::new();
         while reader.read_line(&mut line).await? > 0 {
             select! {
                 _ = stop_clone.notified() => break,
                 _ = async {
+                    console_clone.publish(&line);
                     let _ = tx_stdout.send(line.clone()).await;
                     let mut f = stdout_file_clone.lock().await;
                     let _ = f.write_all(line.as_bytes()).await;
@@ -340,13 +480,15 @@ pub async fn server_thread(
     let tx_stderr = tx.clone();
     let stderr_file_clone = stderr_file.clone();
     let stop_clone = stop.clone();
-    let stderr_handle = spawn(async move {
+    let console_clone = console.clone();
+    let mut stderr_handle = spawn(async move {
         let mut reader = BufReader::new(stderr);
         let mut line = String::new();
         while reader.read_line(&mut line).await? > 0 {
             select! {
                 _ = stop_clone.notified() => break,
                 _ = async {
+                    console_clone.publish(&line);
                     let _ = tx_stderr.send(line.clone()).await;
                     let mut f = stderr_file_clone.lock().await;
                     let _ = f.write_all(line.as_bytes()).await;
@@ -357,58 +499,164 @@ pub async fn server_thread(
         Ok::<(), Error>(())
     });
 
-    // rx -> stdin
-    let child_stdin_clone = child_stdin.clone();
-    let stop_clone = stop.clone();
-    let stdin_handle = spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            select! {
-                _ = stop_clone.notified() => break,
-                _ = async {
-                    let mut stdin = child_stdin_clone.lock().await;
-                    let _ = stdin.write_all(msg.as_bytes()).await;
-                    let _ = stdin.write_all(b"\n").await;
-                } => {}
-            }
-        }
-        Ok::<(), Error>(())
-    });
-
-    // 等待子进程结束或停止信号
-    select! {
+    // 等待子进程结束、停止信号或重启请求
+    let exit = select! {
         _ = stop.notified() => {
-            let mut stdin = child_stdin.lock().await;
-            let _ = stdin.write_all(b"stop\n").await;
-            let _ = stdin.flush().await;
-            info!("Stopping server...");
-            match tokio::time::timeout(std::time::Duration::from_secs(10), child.wait()).await {
-                Ok(Ok(_)) => info!("Server exited gracefully."),
-                Ok(Err(e)) => error!("Error waiting for server exit: {}", e),
-                Err(_) => {
-                    warn!("Server did not exit in 10s, killing...");
-                    let _ = child.kill().await;
-                    let _ = child.wait().await;
-                }
-            }
+            stop_server(&mut child, config, console, None).await;
+            console.detach().await;
+            Exit::Stopped
+        }
+        countdown = console.restart_requested() => {
+            info!("Restart requested");
+            stop_server(&mut child, config, console, countdown).await;
+            console.detach().await;
+            Exit::Restart
         }
         status = child.wait() => {
-            let status = status?;
-            stop.notify_waiters();
-            info!("Server exited: {:?}", status.code());
+            // 读取剩余的输出，崩溃的原因通常在最后几行
+            let _ = tokio::time::timeout(Duration::from_secs(1), async {
+                let _ = (&mut stdout_handle).await;
+                let _ = (&mut stderr_handle).await;
+            })
+            .await;
+            console.detach().await;
+            Exit::Exited(status?)
         }
-    }
+    };
 
     // 等待所有线程完成
     stdout_handle.abort();
     stderr_handle.abort();
-    stdin_handle.abort();
-    drop(tx);
+    ready_handle.abort();
 
-    Ok(())
+    Ok(exit)
+}
+
+/// 按 `[runtime.shutdown]` 停止服务端，依次广播倒计时、发送停止命令、SIGTERM 和 SIGKILL，
+/// `countdown` 不为空时代替配置中的倒计时
+async fn stop_server(
+    child: &mut Child,
+    config: &Config,
+    console: &Console,
+    countdown: Option<u64>,
+) {
+    console.set_state(ServerState::Stopping);
+    let mut shutdown = config.runtime.shutdown.clone().unwrap_or_default();
+    if let Some(countdown) = countdown {
+        shutdown.countdown = countdown;
+    }
+    let server_type = &config.project.server_type;
+
+    // 倒计时，服务端在此期间退出时直接返回
+    let marks = shutdown.countdown_marks();
+    for (i, remaining) in marks.iter().enumerate() {
+        if let Some(command) =
+            server_type.broadcast_command(&shutdown.message.replace("{}", &remaining.to_string()))
+        {
+            let _ = console.send(&command).await;
+        }
+        let next = marks.get(i + 1).copied().unwrap_or(0);
+        select! {
+            _ = child.wait() => return,
+            _ = tokio::time::sleep(Duration::from_secs(remaining - next)) => {}
+        }
+    }
+
+    info!("Stopping server...");
+    let _ = console.send(shutdown.command(server_type)).await;
+    let timeout = shutdown.timeout(server_type);
+    if wait_exit(child, timeout).await {
+        info!("Server exited gracefully.");
+        return;
+    }
+
+    // 服务端没有响应停止命令时先发送 SIGTERM，仍未退出再强制结束
+    #[cfg(target_family = "unix")]
+    if let Some(id) = child.id() {
+        use nix::sys::signal::{Signal, kill};
+        use nix::unistd::Pid;
+        warn!(
+            "Server did not exit in {}s, sending SIGTERM...",
+            timeout.as_secs()
+        );
+        let _ = kill(Pid::from_raw(id as i32), Signal::SIGTERM);
+        if wait_exit(child, Duration::from_secs(shutdown.term_timeout)).await {
+            info!("Server exited after SIGTERM.");
+            return;
+        }
+    }
+    warn!("Server did not exit, killing...");
+    let _ = child.kill().await;
+    let _ = child.wait().await;
+}
+
+/// 等待服务端退出，超时返回 false
+async fn wait_exit(child: &mut Child, timeout: Duration) -> bool {
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            error!("Error waiting for server exit: {}", e);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// 将服务端的退出状态和最后的输出记录到 `{LOG_DIR}/restart.log`
+async fn record_exit(status: ExitStatus, action: &str, console: &Console) {
+    let mut record = format!(
+        "[{}] The server exited with {}, {}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        status,
+        action
+    );
+    for line in console.history() {
+        record.push_str("    ");
+        record.push_str(line.trim_end_matches(['\r', '\n']));
+        record.push('\n');
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{}/restart.log", LOG_DIR))
+        .await;
+    if let Err(e) = match file {
+        Ok(mut file) => file.write_all(record.as_bytes()).await,
+        Err(e) => Err(e),
+    } {
+        error!("Failed to record the exit of the server: {}", e);
+    }
+}
+
+/// 将日志事件逐行以 JSON 格式追加到 `{LOG_DIR}/events.log`
+async fn record_events(mut events: broadcast::Receiver<Event>) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{}/events.log", LOG_DIR))
+        .await?;
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(n)) => {
+                warn!("{} log events were dropped", n);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        debug!("Log event: {:?}", event);
+        let mut record = serde_json::to_value(&event)?;
+        record["time"] = serde_json::Value::String(Local::now().to_rfc3339());
+        file.write_all(format!("{}\n", record).as_bytes()).await?;
+    }
 }
 
 /// 备份线程
-pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(), Error> {
+pub async fn backup_thread(
+    config: Arc<Config>,
+    stop: Arc<Notify>,
+    console: Arc<Console>,
+) -> Result<(), Error> {
     if !config.backup.enable {
         return Ok(());
     }
@@ -416,27 +664,24 @@ pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(),
     let mut backup_handles = vec![];
     info!("Backup task enabled");
     // 初始化仓库
-    let init_handle_world: JoinHandle<Result<_, Error>> = spawn(async {
-        if backup_check_repo(format!("{}/world", BACKUP_DIR).as_str()).is_err() {
-            backup_init_repo(format!("{}/world", BACKUP_DIR).as_str())?;
-        }
-        Ok(())
-    });
-    let init_handle_other: JoinHandle<Result<_, Error>> = spawn(async {
-        if backup_check_repo(format!("{}/other", BACKUP_DIR).as_str()).is_err() {
-            backup_init_repo(format!("{}/other", BACKUP_DIR).as_str())?;
-        }
-        Ok(())
-    });
-    init_handle_world.await??;
-    init_handle_other.await??;
+    let (world, other) = config.backup.targets();
+    let mut init_handles: Vec<JoinHandle<Result<_, Error>>> = vec![];
+    for repo in [world, other].into_iter().flatten() {
+        init_handles.push(spawn(async move { init_repository(&repo) }));
+    }
+    for handle in init_handles {
+        handle.await??;
+    }
     // 启动时备份
     if config.backup.event.is_some() && config.backup.event.as_ref().unwrap().start {
         info!("Backup is enabled at start");
+        let (world, other) = config.backup.targets();
         backup_handles.push(spawn(run_backup(
             "Start",
-            config.backup.world,
-            config.backup.other,
+            world,
+            other,
+            config.backup.retention.clone(),
+            console.clone(),
         )))
     }
     // 时间备份
@@ -445,6 +690,7 @@ pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(),
             info!("Cron backup enabled");
             let stop = stop.clone();
             let config = Arc::clone(&config);
+            let console = console.clone();
             backup_handles.push(spawn(async move {
                 // 配置 Cron 备份
                 let mut cron = AsyncCron::new(Local);
@@ -452,9 +698,17 @@ pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(),
                     let config = Arc::clone(&config); // clone 一份给闭包
                     move || {
                         let config = Arc::clone(&config); // async move 闭包内部再 clone
+                        let console = console.clone();
                         async move {
-                            let _ =
-                                run_backup("Corn", config.backup.world, config.backup.other).await;
+                            let (world, other) = config.backup.targets();
+                            let _ = run_backup(
+                                "Corn",
+                                world,
+                                other,
+                                config.backup.retention.clone(),
+                                console,
+                            )
+                            .await;
                         }
                     }
                 })
@@ -474,15 +728,17 @@ pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(),
             // 开始间隔备份
             let stop = stop.clone();
             let config = Arc::clone(&config);
+            let console = console.clone();
             backup_handles.push(spawn(async move {
                 loop {
+                    let (world, other) = config.backup.targets();
                     select! {
                         _ = stop.notified() => {
                             // 等待 Stop
                             info!("Stop signal received. Exiting interval backup loop.");
                             break Ok(());
                         }
-                        result = run_backup("Interval", config.backup.world, config.backup.other) => {
+                        result = run_backup("Interval", world, other, config.backup.retention.clone(), console.clone()) => {
                             if let Err(e) = result {
                                 error!("Backup failed: {:?}", e);
                             }
@@ -503,10 +759,19 @@ pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(),
     debug!("[Backup] Wait for stop signal");
     // 等待 Stop
     stop.notified().await;
-    // 停止时备份
+    // 停止时备份，等待服务端退出后再备份，确保存档已经全部写入
     if config.backup.event.is_some() && config.backup.event.as_ref().unwrap().stop {
         info!("Backup is enabled at stop");
-        run_backup("Stop", config.backup.world, config.backup.other).await?;
+        console.wait_stopped().await;
+        let (world, other) = config.backup.targets();
+        run_backup(
+            "Stop",
+            world,
+            other,
+            config.backup.retention.clone(),
+            console,
+        )
+        .await?;
     }
     info!("Backup task stopping...");
     for i in backup_handles {
@@ -516,25 +781,151 @@ pub async fn backup_thread(config: Arc<Config>, stop: Arc<Notify>) -> Result<(),
     Ok(())
 }
 
-/// 运行备份
-async fn run_backup(tag: &str, world: bool, other: bool) -> Result<(), Error> {
+/// 计划任务线程，按 `[[schedule]]` 的 Cron 表达式执行操作
+pub async fn schedule_thread(
+    config: Arc<Config>,
+    stop: Arc<Notify>,
+    console: Arc<Console>,
+) -> Result<(), Error> {
+    if config.schedule.is_empty() {
+        return Ok(());
+    }
+
+    let mut cron = AsyncCron::new(Local);
+    for schedule in config.schedule.iter().cloned() {
+        let cron_expr = schedule.cron.trim().to_string();
+        let schedule = Arc::new(schedule);
+        let config = Arc::clone(&config);
+        let console = console.clone();
+        cron.add_fn(&cron_expr, move || {
+            let schedule = Arc::clone(&schedule);
+            let config = Arc::clone(&config);
+            let console = console.clone();
+            async move {
+                debug!(
+                    "Scheduled task `{}` executed at: {}",
+                    schedule.cron,
+                    Local::now()
+                );
+                if let Err(e) = run_schedule(&schedule, &config, console).await {
+                    error!("Scheduled task `{}` failed: {}", schedule.cron, e);
+                }
+            }
+        })
+        .await
+        .map_err(|e| Error::msg(format!("Invalid cron `{}`: {}", cron_expr, e)))?;
+    }
+    info!("{} scheduled tasks enabled", config.schedule.len());
+    // 开始计划任务
+    cron.start().await;
+    // 等待 Stop
+    stop.notified().await;
+    // 停止计划任务
+    cron.stop().await;
+    debug!("Schedule task stopped.");
+    Ok(())
+}
+
+/// 执行一个计划任务
+async fn run_schedule(
+    schedule: &Schedule,
+    config: &Config,
+    console: Arc<Console>,
+) -> Result<(), Error> {
+    match &schedule.action {
+        ScheduleAction::Command { commands } => {
+            for command in commands {
+                console.send(command).await?;
+            }
+        }
+        ScheduleAction::Restart { countdown } => console.request_restart(*countdown)?,
+        ScheduleAction::Backup => {
+            let (world, other) = config.backup.targets();
+            if world.is_none() && other.is_none() {
+                return Err(Error::msg("No repository is enabled in [backup]"));
+            }
+            for repo in world.iter().chain(other.iter()) {
+                init_repository(repo)?;
+            }
+            run_backup(
+                "Schedule",
+                world,
+                other,
+                config.backup.retention.clone(),
+                console,
+            )
+            .await?;
+        }
+        ScheduleAction::Hook { command } => {
+            #[cfg(target_family = "unix")]
+            let mut hook = Command::new("sh");
+            #[cfg(target_family = "unix")]
+            hook.arg("-c");
+            #[cfg(target_family = "windows")]
+            let mut hook = Command::new("cmd");
+            #[cfg(target_family = "windows")]
+            hook.arg("/C");
+            let status = hook.arg(command).stdin(Stdio::null()).status().await?;
+            if !status.success() {
+                return Err(Error::msg(format!("`{}` exited with {}", command, status)));
+            }
+            info!("Hook `{}` finished", command);
+        }
+    }
+    Ok(())
+}
+
+/// 运行备份，`world` 和 `other` 为需要备份的仓库，
+/// `console` 连接的服务端正在运行时，备份世界前先暂停自动保存
+pub(crate) async fn run_backup(
+    tag: &str,
+    world: Option<BackupRepository>,
+    other: Option<BackupRepository>,
+    retention: Option<Retention>,
+    console: Arc<Console>,
+) -> Result<(), Error> {
     debug!("{} backup job executed at: {}", tag, Local::now());
     let mut handles = vec![];
     let tag_arc = Arc::new(tag.to_string());
-    if world {
+    let retention = Arc::new(retention);
+    if let Some(repo) = world {
         let tag = Arc::clone(&tag_arc);
+        let retention = Arc::clone(&retention);
+        let console = Arc::clone(&console);
         handles.push(spawn(async move {
-            // 运行备份
-            backup_new_snap(
-                format!("{}/world", BACKUP_DIR).as_str(),
-                tag.as_ref(),
-                vec!["world".parse()?],
-            )?;
+            let world_dir = console.server_type().world_dir();
+            // 服务端仍在写入区块时备份可能得到损坏的存档，先让服务端保存并暂停自动保存
+            match console.hold_saving().await {
+                // BDS 在暂停期间复制文件，复制完成后即可恢复保存
+                Some(Held::Files(files)) => {
+                    let stage = stage_world(world_dir, tag.as_ref(), &files);
+                    console.resume_saving().await;
+                    let (stage, level_dir) = stage?;
+                    let result = backup_new_snap(
+                        &repo.backend,
+                        tag.as_ref(),
+                        vec![stage.clone()],
+                        Some(&level_dir),
+                    );
+                    let _ = fs::remove_dir_all(&stage);
+                    result?;
+                }
+                Some(Held::Saved) => {
+                    let result =
+                        backup_new_snap(&repo.backend, tag.as_ref(), vec![world_dir.into()], None);
+                    console.resume_saving().await;
+                    result?;
+                }
+                None => backup_new_snap(&repo.backend, tag.as_ref(), vec![world_dir.into()], None)?,
+            }
+            after_backup(&repo, retention.as_ref().as_ref())?;
             Ok::<(), Error>(())
         }))
     }
-    if other {
+    if let Some(repo) = other {
         let tag = Arc::clone(&tag_arc);
+        let retention = Arc::clone(&retention);
+        let world_dir = console.server_type().world_dir();
         handles.push(spawn(async move {
             // 构建路径列表
             let mut dir_list = tokio::fs::read_dir(env::current_dir()?).await?;
@@ -543,97 +934,257 @@ async fn run_backup(tag: &str, world: bool, other: bool) -> Result<(), Error> {
                 let path = entry.path();
                 if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
                     // 排除目录
-                    if file_name != WORK_DIR && file_name != "world" {
+                    if file_name != WORK_DIR && file_name != world_dir {
                         path_list.push(path);
                     }
                 }
             }
             // 运行备份
-            backup_new_snap(
-                format!("{}/other", BACKUP_DIR).as_str(),
-                tag.as_ref(),
-                path_list,
-            )?;
+            backup_new_snap(&repo.backend, tag.as_ref(), path_list, None)?;
+            after_backup(&repo, retention.as_ref().as_ref())?;
             Ok::<(), Error>(())
         }))
     }
-    join_all(handles).await;
+    for result in join_all(handles).await {
+        result??;
+    }
     Ok(())
 }
 
-/// 运行前准备工作
-pub fn pre_run(config: &Config) -> Result<(), Error> {
-    // 准备基岩版
-    if let ServerType::BDS = config.project.server_type {
-        debug!("Prepare the Bedrock Edition server");
-        // 检查文件是否存在
-        let mime_type = get_mime_type(Path::new(&config.project.execute));
-        if mime_type == "application/x-executable" && env::consts::OS == "linux" {
-            return Ok(());
-        }
-        if mime_type == "application/vnd.microsoft.portable-executable"
-            && env::consts::OS == "windows"
-        {
-            return Ok(());
+/// 将 BDS 报告的文件复制到缓存目录中并截断到对应的长度，
+/// 返回复制后的存档目录和存档的路径 `worlds/<存档名称>`，快照中只记录正在运行的存档
+fn stage_world(
+    world_dir: &str,
+    tag: &str,
+    files: &[(PathBuf, u64)],
+) -> Result<(PathBuf, PathBuf), Error> {
+    // 文件的路径均以存档名称开头
+    let level = files
+        .first()
+        .and_then(|(path, _)| path.components().next())
+        .map(|x| PathBuf::from(x.as_os_str()))
+        .ok_or(Error::msg("The server did not report any file"))?;
+    let level_dir = Path::new(world_dir).join(&level);
+    let stage = Path::new(CACHE_DIR)
+        .join("snapshot")
+        .join(tag)
+        .join(&level_dir);
+    if stage.exists() {
+        fs::remove_dir_all(&stage)?;
+    }
+    for (path, length) in files {
+        let destination = stage.join(path.strip_prefix(&level).map_err(|_| {
+            Error::msg(format!(
+                "{} is not in the world {}",
+                path.display(),
+                level.display()
+            ))
+        })?);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
         }
-        // 备份有问题的文件/目录
-        if Path::new(&config.project.execute).exists() {
-            debug!("The file exists but has problems. Make a backup.");
-            fs::rename(
-                Path::new(&config.project.execute),
-                Path::new(&format!("{:?}.bak", config.project.execute)),
-            )?
+        fs::copy(Path::new(world_dir).join(path), &destination)?;
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&destination)?
+            .set_len(*length)?;
+    }
+    Ok((stage, level_dir))
+}
+
+/// 备份后按保留策略清理仓库，并将快照同步到副本仓库
+fn after_backup(repo: &BackupRepository, retention: Option<&Retention>) -> Result<(), Error> {
+    apply_retention(&repo.backend, retention)?;
+    // 副本仓库不可用时不影响本次备份
+    for copy in &repo.copy {
+        let copied = backup_copy_snaps(&repo.backend, copy)
+            .and_then(|copied| apply_retention(copy, retention).map(|_| copied));
+        match copied {
+            Ok(copied) => info!("Copied {} snapshots to {}", copied, copy.repository),
+            Err(e) => error!("Failed to copy snapshots to {}: {}", copy.repository, e),
         }
-        // 安装服务端
-        debug!("Install the Bedrock Edition server");
-        install_bds()?;
+    }
+    Ok(())
+}
+
+/// 按保留策略清理仓库
+fn apply_retention(backend: &BackupBackend, retention: Option<&Retention>) -> Result<(), Error> {
+    let Some(retention) = retention else {
         return Ok(());
+    };
+    let forgotten = backup_apply_retention(backend, retention)?;
+    if forgotten > 0 {
+        info!(
+            "Deleted {} snapshots from {}",
+            forgotten, backend.repository
+        );
     }
-    // 准备 Java 版
-    debug!("Prepare the Java Edition server");
-    let jar_version = analyze_jar(Path::new(&config.project.execute)); //仅判断服务端是否可用，不主动更改版本
-    if jar_version.is_err() {
+    Ok(())
+}
+
+/// 初始化备份仓库及其副本仓库，已存在的仓库不做改动，
+/// 副本仓库不可用时只记录错误，与 `after_backup` 一致
+pub(crate) fn init_repository(repo: &BackupRepository) -> Result<(), Error> {
+    init_backend(&repo.backend)?;
+    for copy in &repo.copy {
+        if let Err(e) = init_backend(copy) {
+            error!(
+                "Failed to initialize the copy repository {}: {}",
+                copy.repository, e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 仓库不存在时初始化
+fn init_backend(backend: &BackupBackend) -> Result<(), Error> {
+    if backup_check_repo(backend).is_err() {
+        info!("Initialize the backup repository {}", backend.repository);
+        backup_init_repo(backend)?;
+    }
+    Ok(())
+}
+
+/// 更新前运行一次备份，仅当启用 `backup.event.update` 时生效
+pub fn backup_before_update(config: &Config) -> Result<(), Error> {
+    if !config.backup.enable || !config.backup.event.as_ref().is_some_and(|x| x.update) {
+        return Ok(());
+    }
+    info!("Backup is enabled at update");
+    // 初始化仓库
+    let (world, other) = config.backup.targets();
+    for repo in world.iter().chain(other.iter()) {
+        init_repository(repo)?;
+    }
+    Runtime::new()?.block_on(run_backup(
+        "Update",
+        world,
+        other,
+        config.backup.retention.clone(),
+        Console::new(config.project.server_type.clone()),
+    ))
+}
+
+/// 运行前准备工作
+pub fn pre_run(config: &Config) -> Result<(), Error> {
+    let provider = provider_for(config);
+    // 读取锁文件
+    let mut lock = Lock::load()?;
+    debug!("Prepare the server");
+    let execute = Path::new(&config.project.execute);
+    let locked_core = lock.core_for(config).cloned();
+    // 锁文件记录的服务端与配置不一致时，说明配置中的版本已被修改，需要重新安装
+    let mismatch = lock.core.is_some() && locked_core.is_none();
+    if mismatch {
+        info!(
+            "The server recorded in {} does not match the configuration",
+            LOCK_FILE
+        );
+    }
+    // 仅判断服务端是否可用且与锁文件一致，不主动更改版本，
+    // 直接运行的服务端记录的是压缩包的哈希值，不校验文件本身
+    let native = provider.launch() == LaunchSpec::Native;
+    let usable = provider.check(execute)
+        && !mismatch
+        && (native || locked_core.as_ref().is_none_or(|x| x.verify(execute)));
+    if !usable {
         // 备份有问题的文件/目录
-        if Path::new(&config.project.execute).exists() {
+        if execute.exists() {
             debug!("The file exists but has problems. Make a backup.");
             fs::rename(
-                Path::new(&config.project.execute),
+                execute,
                 Path::new(&format!("{:?}.bak", config.project.execute)),
             )?
         }
-        // 安装 Java 版服务端
-        debug!("Install the Java Edition server");
-        install_je(VersionInfo::get_version_info(
-            &config.project.version,
-            config.project.server_type.clone(),
-        )?)?;
+        // 安装服务端，优先安装锁文件记录的版本
+        debug!("Install the server");
+        let artifact = match locked_core.as_ref().and_then(|x| x.to_artifact()) {
+            Some(artifact) => {
+                info!("Install the server recorded in {}", LOCK_FILE);
+                artifact
+            }
+            None => provider.resolve(
+                &provider.version_info(&config.project.version)?,
+                config
+                    .project
+                    .build
+                    .or(locked_core.as_ref().and_then(|x| x.build)),
+                locked_core
+                    .as_ref()
+                    .and_then(|x| x.loader.as_deref())
+                    .or(config.project.loader_version.as_deref()),
+                config.project.channel.as_ref(),
+            )?,
+        };
+        let file = provider.install(&artifact, execute)?;
+        lock.core = Some(LockedCore::new(&artifact, &file));
+    } else if lock.core.is_none() && !native {
+        // 记录非 PacMine 安装的服务端，仅在锁文件中没有记录时
+        lock.core = Some(LockedCore::from_file(config, execute)?);
+    }
+    // 写入配置文件中的 server.properties 配置项
+    apply_properties(config)?;
+    // 直接运行的服务端不需要 Java
+    if native {
+        lock.save()?;
+        return Ok(());
     }
     // 准备 Java 运行环境
     debug!("Prepare the Java Runtime");
     // 自动模式
     if let JavaMode::Auto = config.runtime.java.mode {
-        // 分析 Jar 文件需要的 Java 版本
-        let jar_version = analyze_jar(Path::new(&config.project.execute))?;
+        // 模组服务端的启动文件和参数文件不能反映游戏需要的 Java 版本，使用版本清单中的记录
+        let java_version = match config.project.server_type {
+            ServerType::Fabric | ServerType::Quilt => None,
+            _ if provider.launch() == LaunchSpec::Jar => Some(analyze_jar(execute)?.java_version),
+            _ => None,
+        };
+        let java_version = match java_version {
+            // 分析 Jar 文件需要的 Java 版本
+            Some(java_version) => java_version as usize,
+            None => VersionManifest::fetch()?
+                .search(config.project.version.clone())?
+                .java_version()?,
+        };
         // 准备 Java
-        prepare_java(JavaType::OpenJDK, jar_version.java_version as usize)?;
+        prepare_java(JavaType::OpenJDK, java_version)?;
+        lock.java = Some(LockedJava {
+            edition: JavaType::OpenJDK,
+            version: java_version,
+        });
     }
     // 手动模式
     if let JavaMode::Manual = config.runtime.java.mode {
         if let JavaType::Custom = config.runtime.java.edition {
             // 自定义模式
-            return if check_java(Path::new(&config.runtime.java.custom)) {
-                Ok(())
-            } else {
-                Err(Error::msg("The custom Java cannot be used!"))
-            };
+            if !check_java(Path::new(&config.runtime.java.custom)) {
+                return Err(Error::msg("The custom Java cannot be used!"));
+            }
+            lock.java = None;
         } else {
             // 准备 Java
             prepare_java(
                 config.runtime.java.edition.clone(),
                 config.runtime.java.version,
             )?;
+            lock.java = Some(LockedJava {
+                edition: config.runtime.java.edition.clone(),
+                version: config.runtime.java.version,
+            });
+        }
+    }
+    // 安装锁文件记录的插件
+    if config.plugin_manage.manage {
+        debug!("Prepare the plugins");
+        for plugin in &lock.plugin {
+            if config.plugin.iter().any(|x| x.name == plugin.name) {
+                install_locked_plugin(plugin)?;
+            }
         }
     }
+    // 写入锁文件
+    lock.save()?;
     // 准备完成
     debug!("All the work before operation is ready");
     Ok(())
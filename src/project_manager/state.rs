@@ -0,0 +1,143 @@
+use crate::project_manager::events::log_message;
+use crate::project_manager::tools::ServerType;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+/// 服务端的运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerState {
+    /// 未运行
+    Stopped,
+    /// 正在执行运行前的准备工作
+    Preparing,
+    /// 进程已启动，尚未就绪
+    Starting,
+    /// 已就绪，可以接受玩家连接
+    Running,
+    /// 正在停止
+    Stopping,
+    /// 异常退出或准备失败
+    Crashed,
+}
+
+impl ServerState {
+    /// 服务端进程是否在运行或正在准备，停止和崩溃时为 false
+    pub fn is_running(&self) -> bool {
+        !matches!(self, ServerState::Stopped | ServerState::Crashed)
+    }
+}
+
+/// 服务端的状态及其变化的时间
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    /// 当前状态
+    pub state: ServerState,
+    /// 进入当前状态的时间
+    pub since: DateTime<Utc>,
+    /// 最近一次启动进程的时间
+    pub started_at: Option<DateTime<Utc>>,
+    /// 最近一次就绪的时间，进程重新启动时清空
+    pub ready_at: Option<DateTime<Utc>>,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Status {
+            state: ServerState::Stopped,
+            since: Utc::now(),
+            started_at: None,
+            ready_at: None,
+        }
+    }
+}
+
+impl Status {
+    /// 进入新的状态
+    pub fn transition(&mut self, state: ServerState) {
+        let now = Utc::now();
+        match state {
+            ServerState::Starting => {
+                self.started_at = Some(now);
+                self.ready_at = None;
+            }
+            ServerState::Running => self.ready_at = Some(now),
+            _ => (),
+        }
+        self.state = state;
+        self.since = now;
+    }
+}
+
+/// 判断服务端输出的一行是否表示已经就绪，只匹配去掉前缀后消息的开头，
+/// 避免玩家在聊天中发送相同的内容
+pub fn is_ready(server_type: &ServerType, line: &str) -> bool {
+    lazy_static! {
+        // Java 版服务端和 Velocity: "Done (3.456s)! For help, type "help""
+        static ref DONE_RE: Regex = Regex::new(r"^Done \(\d+(\.\d+)?s\)!").unwrap();
+    }
+    let message = log_message(server_type, line.trim_end_matches(['\r', '\n']));
+    match server_type {
+        ServerType::BDS => message.starts_with("Server started."),
+        ServerType::Waterfall | ServerType::BungeeCord => message.starts_with("Listening on /"),
+        _ => DONE_RE.is_match(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ready() {
+        assert!(is_ready(
+            &ServerType::Paper,
+            "[12:00:00 INFO]: Done (3.456s)! For help, type \"help\"\n"
+        ));
+        assert!(is_ready(
+            &ServerType::Vanilla,
+            "[12:00:00] [Server thread/INFO]: Done (12s)! For help, type \"help\""
+        ));
+        assert!(!is_ready(
+            &ServerType::Vanilla,
+            "[12:00:00] [Server thread/INFO]: <Steve> Done (1s)"
+        ));
+        assert!(!is_ready(
+            &ServerType::Vanilla,
+            "[12:00:00] [Server thread/INFO]: <Steve> Done (1s)!"
+        ));
+        assert!(!is_ready(
+            &ServerType::Paper,
+            "[12:00:00 INFO]: [Not Secure] <Steve> Done (1s)! For help, type \"help\""
+        ));
+        assert!(!is_ready(
+            &ServerType::Paper,
+            "[12:00:00 INFO]: [Server] Done (1s)!"
+        ));
+        assert!(is_ready(
+            &ServerType::BDS,
+            "[2025-01-01 12:00:00:000 INFO] Server started."
+        ));
+        assert!(!is_ready(&ServerType::BDS, "Done (3.456s)!"));
+        assert!(is_ready(
+            &ServerType::Waterfall,
+            "12:00:00 [INFO] Listening on /0.0.0.0:25577"
+        ));
+    }
+
+    #[test]
+    fn test_transition() {
+        let mut status = Status::default();
+        assert_eq!(status.state, ServerState::Stopped);
+        status.transition(ServerState::Starting);
+        assert!(status.started_at.is_some() && status.ready_at.is_none());
+        status.transition(ServerState::Running);
+        assert!(status.ready_at.is_some());
+        status.transition(ServerState::Crashed);
+        status.transition(ServerState::Starting);
+        assert_eq!(status.state, ServerState::Starting);
+        assert!(status.ready_at.is_none());
+    }
+}
@@ -1,24 +1,86 @@
+use crate::project_manager::config::{BackupBackend, BackupPassword, Retention};
 use crate::project_manager::{CACHE_DIR, PASSWORD};
 use anyhow::Error;
 use rustic_backend::BackendOptions;
+use rustic_core::repofile::{Node, SnapshotFile};
 use rustic_core::{
-    BackupOptions, CheckOptions, ConfigOptions, KeyOptions, LocalDestination, LsOptions, PathList,
-    Repository, RepositoryOptions, RestoreOptions, SnapshotOptions,
+    BackupOptions, CheckOptions, CommandInput, ConfigOptions, KeepOptions, KeyOptions, LimitOption,
+    LocalDestination, LsOptions, NoProgressBars, OpenStatus, PathList, PruneOptions, Repository,
+    RepositoryOptions, RestoreOptions, SnapshotGroupCriterion, SnapshotOptions, StringList,
 };
-use std::path::PathBuf;
-use tracing::debug;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{debug, info};
+
+/// 快照之间的文件差异
+#[derive(Debug, PartialEq)]
+pub enum SnapshotChange {
+    /// 新增的文件或目录
+    Added(PathBuf),
+    /// 删除的文件或目录
+    Removed(PathBuf),
+    /// 内容发生变化的文件
+    Modified(PathBuf),
+}
+
+/// 转换为 rustic 的后端选项
+fn backend_options(backend: &BackupBackend) -> BackendOptions {
+    let mut opts = BackendOptions::default().repository(backend.repository.as_str());
+    opts.repo_hot = backend.repo_hot.clone();
+    opts.options = backend.options.clone();
+    opts.options_hot = backend.options_hot.clone();
+    opts.options_cold = backend.options_cold.clone();
+    opts
+}
+
+/// 生成仓库选项，未设置密码时使用默认的空密码
+fn repository_options(password: Option<&BackupPassword>) -> Result<RepositoryOptions, Error> {
+    let opts = RepositoryOptions::default().cache_dir(format!("{}/backup", CACHE_DIR));
+    let Some(password) = password else {
+        return Ok(opts.password(PASSWORD));
+    };
+    Ok(match password {
+        BackupPassword {
+            value: Some(value), ..
+        } => opts.password(value.as_str()),
+        BackupPassword { env: Some(env), .. } => opts.password(env::var(env).map_err(|_| {
+            Error::msg(format!(
+                "The environment variable {} of the backup password is not set",
+                env
+            ))
+        })?),
+        BackupPassword {
+            file: Some(file), ..
+        } => opts.password_file(file.clone()),
+        BackupPassword {
+            command: Some(command),
+            ..
+        } => opts.password_command(CommandInput::from_str(command)?),
+        _ => opts.password(PASSWORD),
+    })
+}
+
+/// 打开备份仓库
+fn open_repo(backend: &BackupBackend) -> Result<Repository<NoProgressBars, OpenStatus>, Error> {
+    // Initialize Backends
+    let backends = backend_options(backend).to_backends()?;
+
+    // Open repository
+    let repo_opts = repository_options(backend.password.as_ref())?;
+    Ok(Repository::new(&repo_opts, &backends)?.open()?)
+}
 
 /// 初始化备份仓库
-pub fn backup_init_repo(path: &str) -> Result<(), Error> {
+pub fn backup_init_repo(backend: &BackupBackend) -> Result<(), Error> {
     debug!("backup_init_repo : Initialize backup repository");
 
     // Initialize Backends
-    let backends = BackendOptions::default().repository(path).to_backends()?;
+    let backends = backend_options(backend).to_backends()?;
 
     // Init repository
-    let repo_opts = RepositoryOptions::default()
-        .cache_dir(format!("{}/backup", CACHE_DIR))
-        .password(PASSWORD);
+    let repo_opts = repository_options(backend.password.as_ref())?;
     let key_opts = KeyOptions::default();
     let config_opts = ConfigOptions::default();
     let _repo = Repository::new(&repo_opts, &backends)?.init(&key_opts, &config_opts)?;
@@ -27,23 +89,18 @@ pub fn backup_init_repo(path: &str) -> Result<(), Error> {
     Ok(())
 }
 
-/// 创建快照
-pub fn backup_new_snap(path: &str, tag: &str, source: Vec<PathBuf>) -> Result<(), Error> {
+/// 创建快照，`as_path` 为快照中记录的路径，仅在备份单个目录时使用
+pub fn backup_new_snap(
+    backend: &BackupBackend,
+    tag: &str,
+    source: Vec<PathBuf>,
+    as_path: Option<&Path>,
+) -> Result<(), Error> {
     debug!("backup_new_snap : Create new snapshot");
 
-    // Initialize Backends
-    let backends = BackendOptions::default().repository(path).to_backends()?;
+    let repo = open_repo(backend)?.to_indexed_ids()?;
 
-    // Open repository
-    let repo_opts = RepositoryOptions::default()
-        .cache_dir(format!("{}/backup", CACHE_DIR))
-        .password(PASSWORD);
-
-    let repo = Repository::new(&repo_opts, &backends)?
-        .open()?
-        .to_indexed_ids()?;
-
-    let backup_opts = BackupOptions::default();
+    let backup_opts = BackupOptions::default().as_path(as_path.map(Path::to_path_buf));
     let source = PathList::from_iter(source).sanitize()?;
     let snap = SnapshotOptions::default().add_tags(tag)?.to_snapshot()?;
 
@@ -54,18 +111,18 @@ pub fn backup_new_snap(path: &str, tag: &str, source: Vec<PathBuf>) -> Result<()
     Ok(())
 }
 
+/// 使用配置的密码打开仓库，只检查能否打开
+pub fn backup_open_repo(backend: &BackupBackend) -> Result<(), Error> {
+    debug!("backup_open_repo : Open backup repository");
+    open_repo(backend)?;
+    Ok(())
+}
+
 /// 检查仓库
-pub fn backup_check_repo(path: &str) -> Result<(), Error> {
+pub fn backup_check_repo(backend: &BackupBackend) -> Result<(), Error> {
     debug!("backup_check_repo : Check backup repository");
 
-    // Initialize Backends
-    let backends = BackendOptions::default().repository(path).to_backends()?;
-
-    // Open repository
-    let repo_opts = RepositoryOptions::default()
-        .cache_dir(format!("{}/backup", CACHE_DIR))
-        .password(PASSWORD);
-    let repo = Repository::new(&repo_opts, &backends)?.open()?;
+    let repo = open_repo(backend)?;
 
     // Check repository with standard options but omitting cache checks
     let opts = CheckOptions::default().trust_cache(true);
@@ -73,20 +130,16 @@ pub fn backup_check_repo(path: &str) -> Result<(), Error> {
     Ok(())
 }
 
-/// 恢复快照
-pub fn backup_restore_snap(path: &str, snap: &str, destination: &str) -> Result<(), Error> {
+/// 恢复快照，`snap` 为 `快照[:路径]`，`delete` 为是否删除目标中快照不存在的文件
+pub fn backup_restore_snap(
+    backend: &BackupBackend,
+    snap: &str,
+    destination: &str,
+    delete: bool,
+) -> Result<(), Error> {
     debug!("backup_restore_snap : Restore a snapshot");
 
-    // Initialize Backends
-    let backends = BackendOptions::default().repository(path).to_backends()?;
-
-    // Open repository
-    let repo_opts = RepositoryOptions::default()
-        .cache_dir(format!("{}/backup", CACHE_DIR))
-        .password(PASSWORD);
-    let repo = Repository::new(&repo_opts, &backends)?
-        .open()?
-        .to_indexed()?;
+    let repo = open_repo(backend)?.to_indexed()?;
 
     // use latest snapshot without filtering snapshots
     let node = repo.node_from_snapshot_path(snap, |_| true)?;
@@ -98,7 +151,7 @@ pub fn backup_restore_snap(path: &str, snap: &str, destination: &str) -> Result<
     let create = true; // create destination dir, if it doesn't exist
     let dest = LocalDestination::new(destination, create, !node.is_dir())?;
 
-    let opts = RestoreOptions::default();
+    let opts = RestoreOptions::default().delete(delete);
     let dry_run = false;
     // create restore infos. Note: this also already creates needed dirs in the destination
     let restore_infos = repo.prepare_restore(&opts, ls.clone(), &dest, dry_run)?;
@@ -106,3 +159,238 @@ pub fn backup_restore_snap(path: &str, snap: &str, destination: &str) -> Result<
     repo.restore(restore_infos, &opts, ls, &dest)?;
     Ok(())
 }
+
+/// 列出仓库中的快照，按时间排序
+pub fn backup_list_snaps(backend: &BackupBackend) -> Result<Vec<SnapshotFile>, Error> {
+    debug!("backup_list_snaps : List snapshots");
+    let mut snaps = open_repo(backend)?.get_all_snapshots()?;
+    snaps.sort_by_key(|x| x.time);
+    Ok(snaps)
+}
+
+/// 获取快照，`id` 可以是快照 ID 的前缀或 `latest`
+pub fn backup_get_snap(backend: &BackupBackend, id: &str) -> Result<SnapshotFile, Error> {
+    debug!("backup_get_snap : Get a snapshot");
+    Ok(open_repo(backend)?.get_snapshot_from_str(id, |_| true)?)
+}
+
+/// 删除快照，返回被删除的快照
+pub fn backup_forget_snaps(
+    backend: &BackupBackend,
+    ids: &[String],
+) -> Result<Vec<SnapshotFile>, Error> {
+    debug!("backup_forget_snaps : Forget snapshots");
+    let repo = open_repo(backend)?;
+    let snaps = repo.get_snapshots(ids)?;
+    repo.delete_snapshots(&snaps.iter().map(|x| x.id).collect::<Vec<_>>())?;
+    Ok(snaps)
+}
+
+/// 对比两个快照，返回从 `from` 到 `to` 的变化
+pub fn backup_diff_snaps(
+    backend: &BackupBackend,
+    from: &str,
+    to: &str,
+) -> Result<Vec<SnapshotChange>, Error> {
+    debug!("backup_diff_snaps : Compare snapshots");
+    let repo = open_repo(backend)?.to_indexed()?;
+    let mut trees = vec![];
+    for snap in [from, to] {
+        let node = repo.node_from_snapshot_path(snap, |_| true)?;
+        trees.push(
+            repo.ls(&node, &LsOptions::default())?
+                .collect::<Result<BTreeMap<_, _>, _>>()?,
+        );
+    }
+    let to = trees.pop().unwrap_or_default();
+    let from = trees.pop().unwrap_or_default();
+    Ok(diff_nodes(&from, &to))
+}
+
+/// 按保留策略删除快照并清理仓库，返回删除的快照数量
+pub fn backup_apply_retention(
+    backend: &BackupBackend,
+    retention: &Retention,
+) -> Result<usize, Error> {
+    debug!("backup_apply_retention : Apply the retention policy");
+    let repo = open_repo(backend)?;
+    let mut tags = vec![];
+    for tag in &retention.keep_tags {
+        tags.push(StringList::from_str(tag)?);
+    }
+    let mut forgotten = 0;
+
+    // 按数量保留，所有快照作为一组处理
+    if retention.has_keep_rules() {
+        let count = |x: Option<u32>| x.map(|x| x as i32);
+        let keep = KeepOptions::default()
+            .keep_tags(tags.clone())
+            .keep_last(count(retention.keep_last))
+            .keep_hourly(count(retention.keep_hourly))
+            .keep_daily(count(retention.keep_daily))
+            .keep_weekly(count(retention.keep_weekly))
+            .keep_monthly(count(retention.keep_monthly));
+        let ids = repo
+            .get_forget_snapshots(&keep, SnapshotGroupCriterion::new(), |_| true)?
+            .into_forget_ids();
+        if !ids.is_empty() {
+            repo.delete_snapshots(&ids)?;
+            forgotten += ids.len();
+            repo.prune(
+                &PruneOptions::default(),
+                repo.prune_plan(&PruneOptions::default())?,
+            )?;
+        }
+    }
+
+    // 超出大小上限时从最早的快照开始删除，保留最新的快照和带有保留标签的快照
+    let Some(max_size) = retention.max_size.filter(|x| *x > 0) else {
+        return Ok(forgotten);
+    };
+    let prune_opts = PruneOptions::default()
+        .instant_delete(true)
+        .max_unused(LimitOption::Percentage(0));
+    loop {
+        let size: u64 = repo.infos_files()?.repo.iter().map(|x| x.size).sum();
+        if size <= max_size * 1024 * 1024 {
+            break;
+        }
+        let mut snaps = repo.get_all_snapshots()?;
+        snaps.sort_by_key(|x| x.time);
+        snaps.pop();
+        let Some(oldest) = snaps
+            .into_iter()
+            .find(|x| !tags.iter().any(|tag| x.tags.contains_all(tag)))
+        else {
+            info!(
+                "{} exceeds {} MB, but no snapshot can be deleted",
+                backend.repository, max_size
+            );
+            break;
+        };
+        info!(
+            "{} exceeds {} MB, delete the snapshot created at {}",
+            backend.repository,
+            max_size,
+            oldest.time.format("%Y-%m-%d %H:%M:%S")
+        );
+        repo.delete_snapshots(&[oldest.id])?;
+        forgotten += 1;
+        repo.prune(&prune_opts, repo.prune_plan(&prune_opts)?)?;
+    }
+    Ok(forgotten)
+}
+
+/// 将副本仓库中没有的快照复制过去，返回复制的快照数量
+pub fn backup_copy_snaps(from: &BackupBackend, to: &BackupBackend) -> Result<usize, Error> {
+    debug!("backup_copy_snaps : Copy snapshots");
+    let source = open_repo(from)?.to_indexed()?;
+    let destination = open_repo(to)?.to_indexed_ids()?;
+    let snaps = destination
+        .relevant_copy_snapshots(|_| true, &source.get_all_snapshots()?)?
+        .into_iter()
+        .filter(|x| x.relevant)
+        .map(|x| x.sn)
+        .collect::<Vec<_>>();
+    if !snaps.is_empty() {
+        source.copy(&destination, &snaps)?;
+    }
+    Ok(snaps.len())
+}
+
+/// 更换仓库的密码，添加新密码的密钥后删除当前使用的密钥
+pub fn backup_rekey_repo(backend: &BackupBackend, password: &BackupPassword) -> Result<(), Error> {
+    debug!("backup_rekey_repo : Change the repository password");
+    let repo = open_repo(backend)?;
+    let new_opts = repository_options(Some(password))?;
+    let new_password = new_opts
+        .evaluate_password()?
+        .filter(|x| !x.is_empty())
+        .ok_or(Error::msg("The new password is empty"))?;
+    let old_key = *repo.key_id();
+    repo.add_key(&new_password, &KeyOptions::default())?;
+
+    // 当前使用的密钥不能删除，使用新密码重新打开仓库
+    let backends = backend_options(backend).to_backends()?;
+    let repo = Repository::new(&new_opts, &backends)?.open()?;
+    repo.delete_key(&old_key)?;
+    Ok(())
+}
+
+/// 对比两个文件树
+fn diff_nodes(from: &BTreeMap<PathBuf, Node>, to: &BTreeMap<PathBuf, Node>) -> Vec<SnapshotChange> {
+    let mut changes = vec![];
+    for (path, node) in from {
+        match to.get(path) {
+            None => changes.push(SnapshotChange::Removed(path.clone())),
+            // 目录的变化由其中的文件体现
+            Some(new) if new.node_type != node.node_type => {
+                changes.push(SnapshotChange::Modified(path.clone()))
+            }
+            Some(new) if !node.is_dir() && new.content != node.content => {
+                changes.push(SnapshotChange::Modified(path.clone()))
+            }
+            _ => (),
+        }
+    }
+    for path in to.keys() {
+        if !from.contains_key(path) {
+            changes.push(SnapshotChange::Added(path.clone()));
+        }
+    }
+    changes.sort_by(|a, b| change_path(a).cmp(change_path(b)));
+    changes
+}
+
+/// 变化对应的路径
+fn change_path(change: &SnapshotChange) -> &Path {
+    match change {
+        SnapshotChange::Added(path)
+        | SnapshotChange::Removed(path)
+        | SnapshotChange::Modified(path) => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustic_core::repofile::{Metadata, NodeType};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_diff_nodes() {
+        let node = |name: &str, content: Option<&str>| {
+            let mut node = Node::new_node(
+                OsStr::new(name),
+                if content.is_some() {
+                    NodeType::File
+                } else {
+                    NodeType::Dir
+                },
+                Metadata::default(),
+            );
+            node.content = content.map(|x| vec![x.parse().unwrap()]);
+            (PathBuf::from(name), node)
+        };
+        let a = "0".repeat(64);
+        let b = "1".repeat(64);
+        let from = BTreeMap::from([
+            node("world", None),
+            node("world/level.dat", Some(&a)),
+            node("world/session.lock", Some(&a)),
+        ]);
+        let to = BTreeMap::from([
+            node("world", None),
+            node("world/level.dat", Some(&b)),
+            node("world/region", None),
+        ]);
+        assert_eq!(
+            diff_nodes(&from, &to),
+            vec![
+                SnapshotChange::Modified(PathBuf::from("world/level.dat")),
+                SnapshotChange::Added(PathBuf::from("world/region")),
+                SnapshotChange::Removed(PathBuf::from("world/session.lock")),
+            ]
+        );
+    }
+}
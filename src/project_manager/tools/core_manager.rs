@@ -1,155 +1,1212 @@
 use crate::project_manager::config::ServerType;
-use crate::project_manager::tools::downloader::download_file_single_thread;
+use crate::project_manager::config::{BuildChannel, JavaType};
+use crate::project_manager::tools::downloader::{FileDownloadResult, download_file_single_thread};
 use crate::project_manager::tools::version_parser::PaperProject;
-use crate::project_manager::tools::{VersionInfo, VersionManifest, download_files};
+use crate::project_manager::tools::{VersionInfo, VersionManifest, VersionType, download_files};
+use crate::project_manager::tools::{hash_file, java_binary, prepare_java, runtime_path};
 use crate::project_manager::{
-    CACHE_DIR, DEFAULT_DOWNLOAD_THREAD, FOLIA_PROJECT_API, LEAVES_PROJECT_API, PAPER_PROJECT_API,
-    PURPUR_PROJECT_API,
+    BDS_ARCHIVE, BDS_DOWNLOAD_API, BDS_DOWNLOAD_URL, BUNGEECORD_JOB, CACHE_DIR,
+    DEFAULT_DOWNLOAD_THREAD, FABRIC_META_API, FILL_API, FORGE_MAVEN, FORGE_PROMOTIONS_API,
+    LEAVES_PROJECT_API, NEOFORGE_MAVEN, NEOFORGE_VERSIONS_API, PURPUR_PROJECT_API, QUILT_META_API,
+    USER_AGENT,
 };
 use anyhow::Error;
+use lazy_static::lazy_static;
+use md5::{Digest, Md5};
+use regex::Regex;
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use tracing::{error, info, warn};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, error, info, warn};
+use zip::ZipArchive;
 
-/// 安装 Bedrock Edition 服务端
-pub fn install_bds() -> Result<(), Error> {
-    todo!()
+/// Bedrock Edition 服务端的可执行文件
+#[cfg(not(target_os = "windows"))]
+pub const BDS_EXECUTABLE: &str = "bedrock_server";
+/// Bedrock Edition 服务端的可执行文件
+#[cfg(target_os = "windows")]
+pub const BDS_EXECUTABLE: &str = "bedrock_server.exe";
+
+/// 重新安装 Bedrock Edition 服务端时保留的文件和目录
+const BDS_PRESERVED: [&str; 4] = [
+    "server.properties",
+    "allowlist.json",
+    "permissions.json",
+    "worlds/",
+];
+
+/// 解析完成、等待安装的服务端核心
+#[derive(Debug, Clone)]
+pub struct CoreArtifact {
+    /// 服务端类型
+    pub server_type: ServerType,
+    /// 游戏版本
+    pub version: String,
+    /// 构建号，仅 Paper 类服务端存在
+    pub build: Option<usize>,
+    /// 模组加载器版本，仅模组服务端存在
+    pub loader: Option<String>,
+    /// 下载链接，使用安装器的服务端为安装器的下载链接
+    pub url: String,
+    /// 来源提供的 MD5
+    pub md5: Option<String>,
+    /// 来源提供的 SHA1
+    pub sha1: Option<String>,
+    /// 来源提供的 SHA256
+    pub sha256: Option<String>,
+    /// 构建包含的提交，仅 PaperMC 的服务端存在
+    pub changelog: Vec<String>,
 }
 
-/// 安装 Java Edition 服务端
-pub fn install_je(version_info: VersionInfo) -> Result<(), Error> {
+/// 解析 Java Edition 服务端的下载地址，`build` 为指定的构建号，`loader` 为指定的模组加载器版本，
+/// `channel` 为可接受的最低构建通道，缺省时使用最新版本
+pub fn resolve_je(
+    version_info: &VersionInfo,
+    build: Option<usize>,
+    loader: Option<&str>,
+    channel: Option<&BuildChannel>,
+) -> Result<CoreArtifact, Error> {
+    let version = version_info.name.clone();
+    let server_type = version_info.server_type.clone();
     match version_info.server_type {
-        ServerType::Vanilla => vanilla(version_info.name)?,
-        ServerType::Paper => paper_like(PAPER_PROJECT_API, version_info.name)?,
-        ServerType::Folia => paper_like(FOLIA_PROJECT_API, version_info.name)?,
-        ServerType::Purpur => paper_like(PURPUR_PROJECT_API, version_info.name)?,
-        ServerType::Leaves => paper_like(LEAVES_PROJECT_API, version_info.name)?,
-        ServerType::Other => info!("No server is installed."),
-        _ => unreachable!("不存在的服务端类型"),
+        ServerType::Fabric => fabric(version, loader),
+        ServerType::Quilt => quilt(version, loader),
+        ServerType::Forge => forge(version, loader),
+        ServerType::NeoForge => neoforge(version, loader),
+        ServerType::Paper | ServerType::Folia | ServerType::Velocity | ServerType::Waterfall => {
+            fill(server_type, version, build, channel)
+        }
+        ServerType::BungeeCord => bungeecord(version, build),
+        ServerType::Vanilla => vanilla(version),
+        ServerType::Purpur => purpur(version, build),
+        ServerType::Leaves => leaves(version, build, channel),
+        ServerType::Other => Err(Error::msg(
+            "Other servers cannot be installed automatically",
+        )),
+        ServerType::BDS => unreachable!("基岩版服务端使用 resolve_bds"),
+    }
+}
+
+/// 解析 Bedrock Edition 服务端的下载地址，`version` 为 latest 时使用最新版本
+pub fn resolve_bds(version: &str) -> Result<CoreArtifact, Error> {
+    let version = if version == "latest" {
+        latest_bds_version()?
+    } else {
+        VersionInfo::get_version_info(version, ServerType::BDS)?.name
     };
-    Ok(())
+    Ok(CoreArtifact {
+        server_type: ServerType::BDS,
+        url: format!(
+            "{}/{}/bedrock-server-{}.zip",
+            BDS_DOWNLOAD_URL,
+            bds_platform()?.1,
+            version
+        ),
+        version,
+        build: None,
+        loader: None,
+        md5: None,
+        sha1: None,
+        sha256: None,
+        changelog: vec![],
+    })
 }
 
-/// 安装 Vanilla
-fn vanilla(version: String) -> Result<(), Error> {
-    // 下载版本清单
-    let manifest = VersionManifest::fetch()?;
-    // 获得下载链接
-    let (url, sha1) = manifest.search(version)?.to_download()?;
-    // 下载文件
-    let files = download_files(
-        vec![url],
-        format!("{}/download", CACHE_DIR).as_str(),
-        DEFAULT_DOWNLOAD_THREAD,
-    );
+/// 用于解析 Bedrock Edition 下载链接 API 的 JSON
+#[derive(Debug, Deserialize)]
+struct BdsLinks {
+    result: BdsLinksResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct BdsLinksResult {
+    links: Vec<BdsLink>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BdsLink {
+    download_type: String,
+    download_url: String,
+}
+
+/// 获取 Bedrock Edition 服务端的最新版本
+pub fn latest_bds_version() -> Result<String, Error> {
+    lazy_static! {
+        static ref BDS_ZIP_RE: Regex = Regex::new(r"bedrock-server-([\d.]+)\.zip$").unwrap();
+    }
+    let download_type = bds_platform()?.0;
+    let response = reqwest::blocking::Client::new()
+        .get(BDS_DOWNLOAD_API)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .send()?;
+    if !response.status().is_success() {
+        return Err(Error::msg("Request failed"));
+    }
+    let url = response
+        .json::<BdsLinks>()?
+        .result
+        .links
+        .into_iter()
+        .find(|x| x.download_type == download_type)
+        .ok_or(Error::msg("No Bedrock Edition server for this platform"))?
+        .download_url;
+    BDS_ZIP_RE
+        .captures(&url)
+        .map(|x| x[1].to_string())
+        .ok_or(Error::msg("Failed to get the latest version"))
+}
+
+/// 当前平台在下载链接 API 中的类型和下载目录
+fn bds_platform() -> Result<(&'static str, &'static str), Error> {
+    match std::env::consts::OS {
+        "linux" => Ok(("serverBedrockLinux", "bin-linux")),
+        "windows" => Ok(("serverBedrockWindows", "bin-win")),
+        os => Err(Error::msg(format!(
+            "Bedrock Edition servers are not available on {}",
+            os
+        ))),
+    }
+}
+
+/// 获取服务端支持的最新游戏版本，PaperMC 的服务端仅考虑 `channel` 中有构建的版本
+pub fn latest_je_version(
+    server_type: &ServerType,
+    channel: Option<&BuildChannel>,
+) -> Result<String, Error> {
+    let project_api = match server_type {
+        ServerType::Vanilla => return VersionInfo::get_latest_version(VersionType::Release),
+        ServerType::Paper | ServerType::Folia | ServerType::Velocity | ServerType::Waterfall => {
+            return latest_fill_version(server_type, channel);
+        }
+        ServerType::Purpur => {
+            return fetch_json::<PurpurProject>(PURPUR_PROJECT_API)?
+                .versions
+                .last()
+                .cloned()
+                .ok_or(Error::msg("Failed to get the latest version"));
+        }
+        ServerType::Leaves => LEAVES_PROJECT_API,
+        // BungeeCord 只有构建号，没有版本
+        ServerType::BungeeCord => return Ok("latest".to_string()),
+        ServerType::Fabric | ServerType::Quilt => {
+            let meta_api = if let ServerType::Fabric = server_type {
+                FABRIC_META_API
+            } else {
+                QUILT_META_API
+            };
+            return fetch_json::<Vec<MetaVersion>>(&format!("{}/versions/game", meta_api))?
+                .into_iter()
+                .find(|x| x.is_stable())
+                .map(|x| x.version)
+                .ok_or(Error::msg("Failed to get the latest version"));
+        }
+        ServerType::Forge => {
+            return fetch_json::<ForgePromotions>(FORGE_PROMOTIONS_API)?
+                .promos
+                .keys()
+                .filter_map(|x| x.strip_suffix("-latest"))
+                .max_by_key(|x| version_key(x))
+                .map(|x| x.to_string())
+                .ok_or(Error::msg("Failed to get the latest version"));
+        }
+        ServerType::NeoForge => {
+            return fetch_json::<NeoForgeVersions>(NEOFORGE_VERSIONS_API)?
+                .versions
+                .iter()
+                .rev()
+                .find(|x| !x.contains('-'))
+                .and_then(|x| neoforge_game_version(x))
+                .ok_or(Error::msg("Failed to get the latest version"));
+        }
+        _ => return Err(Error::msg("The latest version cannot be determined")),
+    };
+    PaperProject::fetch(project_api)?
+        .versions
+        .last()
+        .cloned()
+        .ok_or(Error::msg("Failed to get the latest version"))
+}
+
+/// 下载并校验服务端核心
+pub fn download_core(artifact: &CoreArtifact) -> Result<FileDownloadResult, Error> {
+    // 下载文件，仅 Mojang 的服务器支持分段下载，其余使用单线程下载
+    let file = if artifact.server_type != ServerType::Vanilla {
+        download_file_single_thread(
+            artifact.url.as_str(),
+            format!("{}/download", CACHE_DIR).as_str(),
+        )?
+    } else {
+        download_files(
+            vec![artifact.url.clone()],
+            format!("{}/download", CACHE_DIR).as_str(),
+            DEFAULT_DOWNLOAD_THREAD,
+        )
+        .into_iter()
+        .next()
+        .ok_or(Error::msg("No files downloaded"))??
+    };
     // 校验文件
-    let file = files
-        .first()
-        .ok_or(Error::msg("No files downloaded"))?
-        .as_ref()
-        .map_err(|e| Error::msg(format!("{:?}", e)))?;
-    if file.sha1 != sha1 {
+    if let Some(md5) = &artifact.md5 {
+        let mut hasher = Md5::new();
+        std::io::copy(&mut fs::File::open(&file.path)?, &mut hasher)?;
+        if md5 != &hex::encode(hasher.finalize()) {
+            return Err(Error::msg("MD5 verification failed"));
+        }
+    }
+    if artifact.sha1.as_ref().is_some_and(|v| v != &file.sha1) {
         return Err(Error::msg("SHA1 verification failed"));
     }
+    if artifact.sha256.as_ref().is_some_and(|v| v != &file.sha256) {
+        return Err(Error::msg("SHA256 verification failed"));
+    }
+    Ok(file)
+}
+
+/// 下载、校验并安装服务端核心到指定位置
+pub fn install_core(artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+    match artifact.server_type {
+        ServerType::Quilt => return install_quilt(artifact, path),
+        ServerType::Forge | ServerType::NeoForge => return install_forge(artifact, path),
+        _ => (),
+    }
+    let mut file = download_core(artifact)?;
     // 清理存在的文件
-    if Path::new("server.jar").exists() {
-        fs::rename("server.jar", "server.jar.bak")?
+    if path.exists() {
+        fs::rename(path, format!("{}.bak", path.display()))?
     }
     // 安装文件
-    fs::rename(file.path.clone(), "server.jar")?;
+    fs::rename(&file.path, path)?;
+    file.path = path.to_path_buf();
+    Ok(file)
+}
+
+/// 下载、校验并安装 Bedrock Edition 服务端到 `path` 所在的目录，保留已有的配置和存档，
+/// 安装的压缩包保存为 `BDS_ARCHIVE`，用于回滚
+pub fn install_bds(artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+    let mut file = download_core(artifact)?;
+    extract_bds(&file.path, path).inspect_err(|_| {
+        fs::remove_file(&file.path).ok();
+    })?;
+    fs::rename(&file.path, BDS_ARCHIVE)?;
+    file.path = PathBuf::from(BDS_ARCHIVE);
+    Ok(file)
+}
+
+/// 将 Bedrock Edition 服务端的压缩包解压到 `path` 所在的目录，保留已有的配置和存档
+pub fn extract_bds(archive: &Path, path: &Path) -> Result<(), Error> {
+    let dir = install_dir(path);
+    let mut archive = ZipArchive::new(fs::File::open(archive)?)?;
+    if archive.index_for_name(BDS_EXECUTABLE).is_none() {
+        return Err(Error::msg(
+            "The downloaded file is not a Bedrock Edition server",
+        ));
+    }
+    // 解压会覆盖现有的安装，先完整读取一遍以校验每个文件的 CRC
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        std::io::copy(&mut entry, &mut std::io::sink())
+            .map_err(|e| Error::msg(format!("The downloaded archive is corrupted: {}", e)))?;
+    }
+    // 解压文件
+    fs::create_dir_all(dir)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        let outpath = dir.join(entry.mangled_name());
+        // 跳过需要保留的文件
+        if BDS_PRESERVED.iter().any(|x| {
+            name.strip_prefix(x)
+                .is_some_and(|rest| rest.is_empty() || x.ends_with('/'))
+        }) && outpath.exists()
+        {
+            debug!("Keep the existing {}", name);
+            continue;
+        }
+        if entry.is_dir() {
+            fs::create_dir_all(&outpath)?;
+        } else {
+            if let Some(parent) = outpath.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut outfile = fs::File::create(&outpath)?;
+            std::io::copy(&mut entry, &mut outfile)?;
+        }
+    }
+    // 添加执行权限
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir.join(BDS_EXECUTABLE), fs::Permissions::from_mode(0o755))?;
+    }
     Ok(())
 }
 
-/// 用于解析 Paper API Version 的 JSON
+/// 解析 Vanilla
+fn vanilla(version: String) -> Result<CoreArtifact, Error> {
+    // 下载版本清单
+    let manifest = VersionManifest::fetch()?;
+    // 获得下载链接
+    let (url, sha1) = manifest.search(version.clone())?.to_download()?;
+    Ok(CoreArtifact {
+        server_type: ServerType::Vanilla,
+        version,
+        build: None,
+        url,
+        loader: None,
+        md5: None,
+        sha1: Some(sha1),
+        sha256: None,
+        changelog: vec![],
+    })
+}
+
+/// 用于解析 Leaves API Builds 的 JSON，与 PaperMC 的 V2 API 相同
 #[derive(Debug, Deserialize)]
-struct PaperVersion {
-    builds: Vec<usize>,
+struct LeavesBuilds {
+    builds: Vec<LeavesBuild>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ApplicationDownload {
+struct LeavesBuild {
+    build: usize,
+    channel: BuildChannel,
+    #[serde(default)]
+    changes: Vec<LeavesChange>,
+    downloads: HashMap<String, LeavesDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LeavesChange {
+    commit: String,
+    summary: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LeavesDownload {
     name: String,
     sha256: String,
 }
 
+/// 解析 Leaves
+fn leaves(
+    version: String,
+    build: Option<usize>,
+    channel: Option<&BuildChannel>,
+) -> Result<CoreArtifact, Error> {
+    let versions = PaperProject::fetch(LEAVES_PROJECT_API)?.versions;
+    if !versions.contains(&version) {
+        print_supported_versions(&versions);
+        return Err(Error::msg("Leaves version does not exist."));
+    }
+    let mut builds = fetch_json::<LeavesBuilds>(&format!(
+        "{}/versions/{}/builds",
+        LEAVES_PROJECT_API, version
+    ))?
+    .builds;
+    builds.sort_by_key(|x| std::cmp::Reverse(x.build));
+    let channel = channel.cloned().unwrap_or_default();
+    let selected = match build {
+        // 使用指定的构建
+        Some(build) => builds
+            .into_iter()
+            .find(|x| x.build == build)
+            .ok_or(Error::msg(format!(
+                "Build {} of {} does not exist",
+                build, version
+            )))?,
+        // 使用通道中最新的构建
+        None => {
+            let selected = builds
+                .into_iter()
+                .find(|x| x.channel >= channel)
+                .ok_or(Error::msg(format!(
+                    "No {:?} build of {} is available, change `channel` to allow less stable builds",
+                    channel, version
+                )))?;
+            info!("Use the latest build {} of {}", selected.build, version);
+            selected
+        }
+    };
+    let download = selected
+        .downloads
+        .get("application")
+        .ok_or(Error::msg("The build has no server download"))?;
+    Ok(CoreArtifact {
+        server_type: ServerType::Leaves,
+        url: format!(
+            "{}/versions/{}/builds/{}/downloads/{}",
+            LEAVES_PROJECT_API, version, selected.build, download.name
+        ),
+        version,
+        build: Some(selected.build),
+        loader: None,
+        md5: None,
+        sha1: None,
+        sha256: Some(download.sha256.clone()),
+        changelog: selected
+            .changes
+            .iter()
+            .map(|x| format!("{} {}", x.commit.get(..7).unwrap_or(&x.commit), x.summary))
+            .collect(),
+    })
+}
+
+/// 用于解析 Purpur API Project 的 JSON
 #[derive(Debug, Deserialize)]
-struct Downloads {
-    application: ApplicationDownload,
+struct PurpurProject {
+    versions: Vec<String>,
 }
 
-/// 用于解析 Paper API Builds 的 JSON
+/// 用于解析 Purpur API Version 的 JSON
 #[derive(Debug, Deserialize)]
-struct PaperBuild {
-    downloads: Downloads,
+struct PurpurVersion {
+    builds: PurpurBuilds,
 }
 
-/// 安装 Paper 类服务端
-fn paper_like(project_api: &str, version: String) -> Result<(), Error> {
-    let version_list = PaperProject::fetch(project_api)?;
-    // 查找版本
-    if version_list
-        .versions
-        .iter()
-        .any(|available_version| &version == available_version)
+#[derive(Debug, Deserialize)]
+struct PurpurBuilds {
+    all: Vec<String>,
+    latest: String,
+}
+
+/// 用于解析 Purpur API Build 的 JSON
+#[derive(Debug, Deserialize)]
+struct PurpurBuild {
+    md5: String,
+    result: String,
+    #[serde(default)]
+    commits: Vec<PurpurCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurCommit {
+    hash: String,
+    description: String,
+}
+
+/// 解析 Purpur，`build` 缺省时使用最新构建
+fn purpur(version: String, build: Option<usize>) -> Result<CoreArtifact, Error> {
+    let versions = fetch_json::<PurpurProject>(PURPUR_PROJECT_API)?.versions;
+    if !versions.contains(&version) {
+        print_supported_versions(&versions);
+        return Err(Error::msg("Purpur version does not exist."));
+    }
+    let builds =
+        fetch_json::<PurpurVersion>(&format!("{}/{}", PURPUR_PROJECT_API, version))?.builds;
+    let build = match build {
+        // 使用指定的构建
+        Some(build) if builds.all.contains(&build.to_string()) => build,
+        Some(build) => {
+            return Err(Error::msg(format!(
+                "Build {} of {} does not exist",
+                build, version
+            )));
+        }
+        // 使用最新的构建
+        None => {
+            let build = builds.latest.parse::<usize>()?;
+            info!("Use the latest build {} of {}", build, version);
+            build
+        }
+    };
+    let build_info =
+        fetch_json::<PurpurBuild>(&format!("{}/{}/{}", PURPUR_PROJECT_API, version, build))?;
+    if build_info.result != "SUCCESS" {
+        return Err(Error::msg(format!(
+            "Build {} of {} is not available: {}",
+            build, version, build_info.result
+        )));
+    }
+    Ok(CoreArtifact {
+        server_type: ServerType::Purpur,
+        url: format!("{}/{}/{}/download", PURPUR_PROJECT_API, version, build),
+        version,
+        build: Some(build),
+        loader: None,
+        md5: Some(build_info.md5),
+        sha1: None,
+        sha256: None,
+        changelog: build_info
+            .commits
+            .iter()
+            .map(|x| {
+                format!(
+                    "{} {}",
+                    x.hash.get(..7).unwrap_or(&x.hash),
+                    x.description.lines().next().unwrap_or_default()
+                )
+            })
+            .collect(),
+    })
+}
+
+/// 获取服务端支持的游戏版本，无法列出时返回 None
+pub fn supported_je_versions(server_type: &ServerType) -> Result<Option<Vec<String>>, Error> {
+    match server_type {
+        ServerType::Paper | ServerType::Folia | ServerType::Velocity | ServerType::Waterfall => {
+            Ok(Some(fill_versions(server_type)?))
+        }
+        ServerType::Purpur => Ok(Some(
+            fetch_json::<PurpurProject>(PURPUR_PROJECT_API)?.versions,
+        )),
+        ServerType::Leaves => Ok(Some(PaperProject::fetch(LEAVES_PROJECT_API)?.versions)),
+        _ => Ok(None),
+    }
+}
+
+/// 输出支持的版本
+pub fn print_supported_versions(versions: &[String]) {
+    error!("Your server type does not support this version");
+    println!("Supported versions:");
+    println!("===================");
+    for i in versions {
+        println!("{}", i)
+    }
+    println!("===================");
+    warn!("Please change the version to a supported version and try again");
+}
+
+/// 用于解析 Fill API Project 的 JSON
+#[derive(Debug, Deserialize)]
+struct FillProject {
+    /// 按版本族分组的版本列表
+    versions: HashMap<String, Vec<String>>,
+}
+
+/// 用于解析 Fill API Builds 的 JSON
+#[derive(Debug, Deserialize)]
+struct FillBuild {
+    id: usize,
+    channel: BuildChannel,
+    #[serde(default)]
+    commits: Vec<FillCommit>,
+    downloads: HashMap<String, FillDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FillCommit {
+    sha: String,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FillDownload {
+    url: String,
+    checksums: FillChecksums,
+}
+
+#[derive(Debug, Deserialize)]
+struct FillChecksums {
+    sha256: String,
+}
+
+/// 服务端在 Fill API 中的项目名称
+fn fill_project(server_type: &ServerType) -> &'static str {
+    match server_type {
+        ServerType::Paper => "paper",
+        ServerType::Folia => "folia",
+        ServerType::Velocity => "velocity",
+        ServerType::Waterfall => "waterfall",
+        _ => unreachable!("不是 PaperMC 的服务端"),
+    }
+}
+
+/// 获取 Fill API 中的版本列表，按从新到旧排列
+fn fill_versions(server_type: &ServerType) -> Result<Vec<String>, Error> {
+    let mut versions: Vec<String> =
+        fetch_json::<FillProject>(&format!("{}/{}", FILL_API, fill_project(server_type)))?
+            .versions
+            .into_values()
+            .flatten()
+            .collect();
+    versions.sort_by_key(|x| std::cmp::Reverse(version_key(x)));
+    Ok(versions)
+}
+
+/// 获取 Fill API 中某个版本的构建列表，按从新到旧排列
+fn fill_builds(server_type: &ServerType, version: &str) -> Result<Vec<FillBuild>, Error> {
+    let mut builds = fetch_json::<Vec<FillBuild>>(&format!(
+        "{}/{}/versions/{}/builds",
+        FILL_API,
+        fill_project(server_type),
+        version
+    ))?;
+    builds.sort_by_key(|x| std::cmp::Reverse(x.id));
+    Ok(builds)
+}
+
+/// 获取 `channel` 中有构建的最新版本
+fn latest_fill_version(
+    server_type: &ServerType,
+    channel: Option<&BuildChannel>,
+) -> Result<String, Error> {
+    let channel = channel.cloned().unwrap_or_default();
+    // 预发布版本不作为最新版本，最多向前查找 5 个版本
+    for version in fill_versions(server_type)?
+        .into_iter()
+        .filter(|x| !x.contains('-'))
+        .take(5)
     {
-        // 创建 Client
-        let client = reqwest::blocking::Client::new();
-        // Versions 列表
-        let response = client
-            .get(format!("{}/versions/{}", project_api, version))
-            .send()?;
-        if !response.status().is_success() {
-            return Err(Error::msg("Request failed"));
-        }
-        let builds = response.json::<PaperVersion>()?;
-        // Builds 列表
-        let response = client
-            .get(format!(
-                "{}/versions/{}/builds/{}",
-                project_api,
-                version,
-                builds.builds.last().expect("No build is available")
-            ))
-            .send()?;
-        if !response.status().is_success() {
-            return Err(Error::msg("Request failed"));
-        }
-        let download_info = response.json::<PaperBuild>()?.downloads.application;
-        // 下载文件，长度未知，使用单线程下载
-        let files = download_file_single_thread(
-            format!(
-                "{}/versions/{}/builds/{}/downloads/{}",
-                project_api,
-                version,
-                builds.builds.last().expect("No build is available"),
-                download_info.name
-            )
-            .as_str(),
-            format!("{}/download", CACHE_DIR).as_str(),
-        );
-        // 校验文件
-        let file = files.map_err(|e| Error::msg(format!("{:?}", e)))?;
-        if file.sha256 != download_info.sha256 {
-            return Err(Error::msg("SHA256 verification failed"));
-        }
-        // 清理存在的文件
-        if Path::new("server.jar").exists() {
-            fs::rename("server.jar", "server.jar.bak")?
-        }
-        // 安装文件
-        fs::rename(file.path.clone(), "server.jar")?;
-        Ok(())
-    } else {
+        if fill_builds(server_type, &version)?
+            .iter()
+            .any(|x| x.channel >= channel)
+        {
+            return Ok(version);
+        }
+    }
+    Err(Error::msg("Failed to get the latest version"))
+}
+
+/// 解析 PaperMC 的服务端
+fn fill(
+    server_type: ServerType,
+    version: String,
+    build: Option<usize>,
+    channel: Option<&BuildChannel>,
+) -> Result<CoreArtifact, Error> {
+    let versions = fill_versions(&server_type)?;
+    if !versions.contains(&version) {
         // 不存在版本时输出支持的版本
-        error!("Your server type does not support this version");
-        println!("Supported versions:");
-        println!("===================");
-        for i in version_list.versions {
-            println!("{}", i)
-        }
-        println!("===================");
-        warn!("Please change the version to a supported version and try again");
-        Err(Error::msg("Paper version does not exist."))
+        print_supported_versions(&versions.into_iter().rev().collect::<Vec<_>>());
+        return Err(Error::msg(format!(
+            "{:?} version does not exist.",
+            server_type
+        )));
+    }
+    let builds = fill_builds(&server_type, &version)?;
+    let channel = channel.cloned().unwrap_or_default();
+    let selected = match build {
+        // 使用指定的构建
+        Some(build) => builds
+            .into_iter()
+            .find(|x| x.id == build)
+            .ok_or(Error::msg(format!(
+                "Build {} of {} does not exist",
+                build, version
+            )))?,
+        // 使用通道中最新的构建
+        None => {
+            let selected = builds
+                .into_iter()
+                .find(|x| x.channel >= channel)
+                .ok_or(Error::msg(format!(
+                    "No {:?} build of {} is available, change `channel` to allow less stable builds",
+                    channel, version
+                )))?;
+            info!(
+                "Use the latest {:?} build {} of {}",
+                selected.channel, selected.id, version
+            );
+            selected
+        }
+    };
+    let download = selected
+        .downloads
+        .get("server:default")
+        .ok_or(Error::msg("The build has no server download"))?;
+    Ok(CoreArtifact {
+        server_type,
+        url: download.url.clone(),
+        version,
+        build: Some(selected.id),
+        loader: None,
+        md5: None,
+        sha1: None,
+        sha256: Some(download.checksums.sha256.clone()),
+        changelog: selected
+            .commits
+            .iter()
+            .map(|x| {
+                format!(
+                    "{} {}",
+                    x.sha.get(..7).unwrap_or(&x.sha),
+                    x.message.lines().next().unwrap_or_default()
+                )
+            })
+            .collect(),
+    })
+}
+
+/// 用于解析 Jenkins 构建的 JSON
+#[derive(Debug, Deserialize)]
+struct JenkinsBuild {
+    number: usize,
+}
+
+/// 解析 BungeeCord，`build` 为 Jenkins 构建号，缺省时使用最新的成功构建
+fn bungeecord(version: String, build: Option<usize>) -> Result<CoreArtifact, Error> {
+    let build = match build {
+        Some(build) => build,
+        None => {
+            let build = fetch_json::<JenkinsBuild>(&format!(
+                "{}/lastSuccessfulBuild/api/json",
+                BUNGEECORD_JOB
+            ))?
+            .number;
+            info!("Use the latest build {} of BungeeCord", build);
+            build
+        }
+    };
+    Ok(CoreArtifact {
+        server_type: ServerType::BungeeCord,
+        url: format!(
+            "{}/{}/artifact/bootstrap/target/BungeeCord.jar",
+            BUNGEECORD_JOB, build
+        ),
+        version,
+        build: Some(build),
+        loader: None,
+        md5: None,
+        sha1: None,
+        sha256: None,
+        changelog: vec![],
+    })
+}
+
+/// 用于解析 Fabric 和 Quilt Meta API 中版本列表的 JSON
+#[derive(Debug, Deserialize)]
+struct MetaVersion {
+    version: String,
+    /// Quilt 的加载器版本没有该字段
+    stable: Option<bool>,
+}
+
+impl MetaVersion {
+    /// 是否为稳定版本，没有标记时以不含预发布后缀为准
+    fn is_stable(&self) -> bool {
+        self.stable.unwrap_or(!self.version.contains('-'))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MetaLoader {
+    loader: MetaVersion,
+}
+
+/// 用于解析 Quilt 安装器列表的 JSON
+#[derive(Debug, Deserialize)]
+struct QuiltInstaller {
+    url: String,
+    version: String,
+}
+
+/// 请求 JSON 格式的 API
+pub fn fetch_json<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
+    let response = reqwest::blocking::Client::new()
+        .get(url)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .send()?;
+    if !response.status().is_success() {
+        return Err(Error::msg(format!("Request failed: {}", response.status())));
+    }
+    Ok(response.json::<T>()?)
+}
+
+/// 选择模组加载器版本，`pinned` 为指定的版本，缺省时使用最新的稳定版本
+fn select_loader(meta_api: &str, version: &str, pinned: Option<&str>) -> Result<String, Error> {
+    let loaders =
+        fetch_json::<Vec<MetaLoader>>(&format!("{}/versions/loader/{}", meta_api, version))
+            .unwrap_or_default();
+    if loaders.is_empty() {
+        return Err(Error::msg(format!(
+            "No loader is available for version {}",
+            version
+        )));
+    }
+    match pinned {
+        Some(pinned) => loaders
+            .into_iter()
+            .find(|x| x.loader.version == pinned)
+            .map(|x| x.loader.version)
+            .ok_or(Error::msg(format!(
+                "Loader {} does not support version {}",
+                pinned, version
+            ))),
+        None => {
+            let loader = loaders
+                .into_iter()
+                .find(|x| x.loader.is_stable())
+                .map(|x| x.loader.version)
+                .ok_or(Error::msg("No stable loader is available"))?;
+            info!("Use the latest loader {} of {}", loader, version);
+            Ok(loader)
+        }
+    }
+}
+
+/// 解析 Fabric
+fn fabric(version: String, loader: Option<&str>) -> Result<CoreArtifact, Error> {
+    let loader = select_loader(FABRIC_META_API, &version, loader)?;
+    // 使用最新的稳定版安装器
+    let installer =
+        fetch_json::<Vec<MetaVersion>>(&format!("{}/versions/installer", FABRIC_META_API))?
+            .into_iter()
+            .find(|x| x.is_stable())
+            .ok_or(Error::msg("No stable installer is available"))?;
+    Ok(CoreArtifact {
+        server_type: ServerType::Fabric,
+        url: format!(
+            "{}/versions/loader/{}/{}/{}/server/jar",
+            FABRIC_META_API, version, loader, installer.version
+        ),
+        version,
+        build: None,
+        loader: Some(loader),
+        md5: None,
+        sha1: None,
+        sha256: None,
+        changelog: vec![],
+    })
+}
+
+/// 解析 Quilt，Quilt 没有提供服务端启动器，需要下载安装器
+fn quilt(version: String, loader: Option<&str>) -> Result<CoreArtifact, Error> {
+    let loader = select_loader(QUILT_META_API, &version, loader)?;
+    let installer =
+        fetch_json::<Vec<QuiltInstaller>>(&format!("{}/versions/installer", QUILT_META_API))?
+            .into_iter()
+            .next()
+            .ok_or(Error::msg("No installer is available"))?;
+    debug!("Use the Quilt installer {}", installer.version);
+    Ok(CoreArtifact {
+        server_type: ServerType::Quilt,
+        url: installer.url,
+        version,
+        build: None,
+        loader: Some(loader),
+        md5: None,
+        sha1: None,
+        sha256: None,
+        changelog: vec![],
+    })
+}
+
+/// 运行 Quilt 安装器安装服务端，返回结果中的哈希值为启动器的哈希值
+fn install_quilt(artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+    // 安装器没有提供哈希值，不做校验
+    let installer = download_file_single_thread(
+        artifact.url.as_str(),
+        format!("{}/download", CACHE_DIR).as_str(),
+    )?;
+    let java = installer_java(&artifact.version)?;
+    // 清理存在的文件
+    if path.exists() {
+        fs::rename(path, format!("{}.bak", path.display()))?
+    }
+    // 运行安装器
+    let dir = install_dir(path);
+    let loader = artifact
+        .loader
+        .as_deref()
+        .ok_or(Error::msg("The loader version is missing"))?;
+    info!("Run the Quilt installer");
+    let status = Command::new(java)
+        .arg("-jar")
+        .arg(&installer.path)
+        .args(["install", "server", &artifact.version, loader])
+        .arg(format!("--install-dir={}", dir.display()))
+        .arg("--download-server")
+        .status()?;
+    fs::remove_file(&installer.path)?;
+    if !status.success() {
+        return Err(Error::msg("The Quilt installer failed"));
+    }
+    // 安装器生成的启动器
+    let launcher = dir.join("quilt-server-launch.jar");
+    if launcher != path {
+        fs::rename(&launcher, path)?;
+    }
+    let (sha256, sha1) = hash_file(path)?;
+    Ok(FileDownloadResult {
+        url: artifact.url.clone(),
+        path: path.to_path_buf(),
+        sha256,
+        sha1,
+    })
+}
+
+/// 准备运行安装器的 Java，使用游戏版本需要的 OpenJDK
+fn installer_java(version: &str) -> Result<PathBuf, Error> {
+    let java_version = VersionManifest::fetch()?
+        .search(version.to_string())?
+        .java_version()?;
+    prepare_java(JavaType::OpenJDK, java_version)?;
+    Ok(java_binary(&runtime_path(&JavaType::OpenJDK, java_version)))
+}
+
+/// 服务端文件所在的目录
+fn install_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// 用于比较版本号的键
+pub fn version_key(version: &str) -> Vec<usize> {
+    version
+        .split(['.', '-'])
+        .map(|x| x.parse().unwrap_or(0))
+        .collect()
+}
+
+/// 用于解析 Forge 推荐版本列表的 JSON
+#[derive(Debug, Deserialize)]
+struct ForgePromotions {
+    promos: HashMap<String, String>,
+}
+
+/// 用于解析 NeoForge 版本列表的 JSON
+#[derive(Debug, Deserialize)]
+struct NeoForgeVersions {
+    versions: Vec<String>,
+}
+
+/// NeoForge 版本对应的游戏版本，21.0.x 对应 1.21，21.1.x 对应 1.21.1
+fn neoforge_game_version(neoforge: &str) -> Option<String> {
+    let mut parts = neoforge.split('.');
+    let (major, minor) = (parts.next()?, parts.next()?);
+    match minor {
+        "0" => Some(format!("1.{}", major)),
+        _ => Some(format!("1.{}.{}", major, minor)),
+    }
+}
+
+/// 解析 Forge，`loader` 缺省时使用推荐版本，没有推荐版本时使用最新版本
+fn forge(version: String, loader: Option<&str>) -> Result<CoreArtifact, Error> {
+    let loader = match loader {
+        Some(loader) => loader.to_string(),
+        None => {
+            let promos = fetch_json::<ForgePromotions>(FORGE_PROMOTIONS_API)?.promos;
+            let loader = promos
+                .get(&format!("{}-recommended", version))
+                .or(promos.get(&format!("{}-latest", version)))
+                .cloned()
+                .ok_or(Error::msg(format!(
+                    "Forge does not support version {}",
+                    version
+                )))?;
+            info!("Use Forge {} of {}", loader, version);
+            loader
+        }
+    };
+    Ok(CoreArtifact {
+        server_type: ServerType::Forge,
+        url: format!(
+            "{}/{v}-{l}/forge-{v}-{l}-installer.jar",
+            FORGE_MAVEN,
+            v = version,
+            l = loader
+        ),
+        version,
+        build: None,
+        loader: Some(loader),
+        md5: None,
+        sha1: None,
+        sha256: None,
+        changelog: vec![],
+    })
+}
+
+/// 解析 NeoForge，`loader` 缺省时使用最新的稳定版本
+fn neoforge(version: String, loader: Option<&str>) -> Result<CoreArtifact, Error> {
+    let versions = fetch_json::<NeoForgeVersions>(NEOFORGE_VERSIONS_API)?.versions;
+    let supported: Vec<&String> = versions
+        .iter()
+        .filter(|x| neoforge_game_version(x).is_some_and(|v| v == version))
+        .collect();
+    let loader = match loader {
+        Some(loader) if supported.iter().any(|x| *x == loader) => loader.to_string(),
+        Some(loader) => {
+            return Err(Error::msg(format!(
+                "NeoForge {} does not support version {}",
+                loader, version
+            )));
+        }
+        None => {
+            let loader = supported
+                .iter()
+                .rev()
+                .find(|x| !x.contains('-'))
+                .or(supported.last())
+                .map(|x| x.to_string())
+                .ok_or(Error::msg(format!(
+                    "NeoForge does not support version {}",
+                    version
+                )))?;
+            info!("Use NeoForge {} of {}", loader, version);
+            loader
+        }
+    };
+    Ok(CoreArtifact {
+        server_type: ServerType::NeoForge,
+        url: format!(
+            "{}/{l}/neoforge-{l}-installer.jar",
+            NEOFORGE_MAVEN,
+            l = loader
+        ),
+        version,
+        build: None,
+        loader: Some(loader),
+        md5: None,
+        sha1: None,
+        sha256: None,
+        changelog: vec![],
+    })
+}
+
+/// 运行 Forge 或 NeoForge 安装器安装服务端，`path` 为生成的参数文件，
+/// 返回结果中的哈希值为参数文件的哈希值
+fn install_forge(artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+    let installer = download_core(artifact)?;
+    let java = installer_java(&artifact.version)?;
+    let dir = install_dir(path);
+    let loader = artifact
+        .loader
+        .as_deref()
+        .ok_or(Error::msg("The loader version is missing"))?;
+    // 运行安装器
+    info!("Run the {:?} installer", artifact.server_type);
+    let status = Command::new(java)
+        .arg("-jar")
+        .arg(&installer.path)
+        .arg("--installServer")
+        .arg(dir)
+        .status()?;
+    fs::remove_file(&installer.path)?;
+    if !status.success() {
+        return Err(Error::msg(format!(
+            "The {:?} installer failed",
+            artifact.server_type
+        )));
+    }
+    // 1.17 及以上版本使用安装器生成的参数文件，旧版本直接运行生成的 jar
+    let args_name = if cfg!(windows) {
+        "win_args.txt"
+    } else {
+        "unix_args.txt"
+    };
+    let args_file = match artifact.server_type {
+        ServerType::NeoForge => dir.join(format!(
+            "libraries/net/neoforged/neoforge/{}/{}",
+            loader, args_name
+        )),
+        _ => dir.join(format!(
+            "libraries/net/minecraftforge/forge/{}-{}/{}",
+            artifact.version, loader, args_name
+        )),
+    };
+    let args = if args_file.is_file() {
+        fs::read_to_string(&args_file)?
+    } else {
+        let jar = [
+            format!("forge-{}-{}.jar", artifact.version, loader),
+            format!("forge-{}-{}-universal.jar", artifact.version, loader),
+        ]
+        .into_iter()
+        .find(|x| dir.join(x).is_file())
+        .ok_or(Error::msg("The installed server cannot be found"))?;
+        format!("-jar {}\n", jar)
+    };
+    // 写入参数文件
+    if path.exists() {
+        fs::rename(path, format!("{}.bak", path.display()))?
+    }
+    fs::write(path, args)?;
+    let (sha256, sha1) = hash_file(path)?;
+    Ok(FileDownloadResult {
+        url: artifact.url.clone(),
+        path: path.to_path_buf(),
+        sha256,
+        sha1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_bds() {
+        use std::io::Write;
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let dir = std::env::temp_dir().join(format!("test-extract-bds-{}", uuid::Uuid::new_v4()));
+        let archive = dir.join("bedrock-server.zip");
+        fs::create_dir_all(dir.join("worlds/level")).unwrap();
+        fs::write(dir.join("server.properties"), "server-port=19133\n").unwrap();
+        fs::write(dir.join("worlds/level/level.dat"), "world").unwrap();
+        let mut zip = ZipWriter::new(fs::File::create(&archive).unwrap());
+        for (name, content) in [
+            (BDS_EXECUTABLE, "new server"),
+            ("server.properties", "server-port=19132\n"),
+            ("worlds/level/level.dat", "template"),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let result = extract_bds(&archive, &dir.join(BDS_EXECUTABLE));
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        let files = (
+            read(BDS_EXECUTABLE),
+            read("server.properties"),
+            read("worlds/level/level.dat"),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        assert_eq!(files.0, "new server");
+        assert_eq!(files.1, "server-port=19133\n");
+        assert_eq!(files.2, "world");
+    }
+
+    #[test]
+    fn test_neoforge_game_version() {
+        assert_eq!(neoforge_game_version("21.0.167").as_deref(), Some("1.21"));
+        assert_eq!(neoforge_game_version("21.1.77").as_deref(), Some("1.21.1"));
+        assert_eq!(
+            neoforge_game_version("20.2.3-beta").as_deref(),
+            Some("1.20.2")
+        );
+        assert_eq!(neoforge_game_version("21"), None);
+    }
+
+    #[test]
+    fn test_fill_build_channel() {
+        let builds: Vec<FillBuild> = serde_json::from_str(
+            r#"[
+                {"id": 10, "channel": "ALPHA", "commits": [], "downloads": {}},
+                {"id": 9, "channel": "STABLE", "commits": [{"sha": "0123456789", "message": "Fix\nBody"}], "downloads": {}}
+            ]"#,
+        )
+        .unwrap();
+        let channel = BuildChannel::default();
+        let selected = builds.iter().find(|x| x.channel >= channel).unwrap();
+        assert_eq!(selected.id, 9);
+        assert!(BuildChannel::Alpha < BuildChannel::Beta);
+        let channel: BuildChannel = serde_json::from_str(r#""experimental""#).unwrap();
+        assert_eq!(channel, BuildChannel::Alpha);
+    }
+
+    #[test]
+    fn test_purpur_build() {
+        let build: PurpurBuild = serde_json::from_str(
+            r#"{"build": "2300", "md5": "d41d8cd98f00b204e9800998ecf8427e", "result": "SUCCESS",
+                "commits": [{"author": "a", "description": "Update\nBody", "hash": "0123456789", "timestamp": 0}]}"#,
+        )
+        .unwrap();
+        assert_eq!(build.result, "SUCCESS");
+        assert_eq!(build.commits[0].description.lines().next(), Some("Update"));
+    }
+
+    #[test]
+    fn test_version_key() {
+        assert!(version_key("1.20.1") > version_key("1.20"));
+        assert!(version_key("1.21") > version_key("1.9.4"));
     }
 }
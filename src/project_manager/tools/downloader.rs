@@ -1,4 +1,4 @@
-use crate::project_manager::MAX_RETRIES;
+use crate::project_manager::{MAX_RETRIES, USER_AGENT};
 use anyhow::Error;
 use futures::future::join_all;
 use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
@@ -36,10 +36,10 @@ pub fn download_files(
 /// 单线程下载文件
 pub fn download_file_single_thread(url: &str, dir: &str) -> Result<FileDownloadResult, Error> {
     fs::create_dir_all(dir)?;
-    let filename = url.split('/').last().unwrap_or("file");
+    let filename = url.split('/').next_back().unwrap_or("file");
     let filepath = Path::new(dir).join(filename);
 
-    let client = blocking::Client::builder().build()?;
+    let client = blocking::Client::builder().user_agent(USER_AGENT).build()?;
     let mut resp = client.get(url).send()?.error_for_status()?;
 
     let mut file = OpenOptions::new()
@@ -55,6 +55,8 @@ pub fn download_file_single_thread(url: &str, dir: &str) -> Result<FileDownloadR
     let mut sha256 = Sha256::new();
     let mut sha1 = Sha1::new();
     let mut buf = [0u8; 8192];
+    let expected = resp.content_length();
+    let mut downloaded = 0u64;
 
     loop {
         let n = resp.read(&mut buf)?;
@@ -64,11 +66,22 @@ pub fn download_file_single_thread(url: &str, dir: &str) -> Result<FileDownloadR
         file.write_all(&buf[..n])?;
         sha256.update(&buf[..n]);
         sha1.update(&buf[..n]);
+        downloaded += n as u64;
         pb.inc(n as u64);
     }
 
     pb.finish_with_message(format!("{} done", filename));
 
+    // 连接中断时读取可能提前结束，检查文件是否完整
+    if expected.is_some_and(|x| x != downloaded) {
+        return Err(Error::msg(format!(
+            "Incomplete download of {}: expected {} bytes, got {}",
+            filename,
+            expected.unwrap_or_default(),
+            downloaded
+        )));
+    }
+
     Ok(FileDownloadResult {
         url: url.to_string(),
         path: filepath,
@@ -145,7 +158,10 @@ async fn download_single(
     dir: &str,
     threads: usize,
 ) -> Result<FileDownloadResult, Error> {
-    let client = Client::builder().use_rustls_tls().build()?;
+    let client = Client::builder()
+        .use_rustls_tls()
+        .user_agent(USER_AGENT)
+        .build()?;
     let resp = client.head(url).send().await?;
     let total_size = resp
         .headers()
@@ -154,13 +170,13 @@ async fn download_single(
         .to_str()?
         .parse::<u64>()?;
 
-    let filename = url.split('/').last().unwrap_or("file");
+    let filename = url.split('/').next_back().unwrap_or("file");
     let filepath = Path::new(dir).join(filename);
     if !filepath.exists() {
         File::create(&filepath)?;
     }
 
-    let chunk_size = (total_size + threads as u64 - 1) / threads as u64;
+    let chunk_size = total_size.div_ceil(threads as u64);
     let file_path = Arc::new(filepath.clone());
 
     // 文件级进度条
@@ -233,7 +249,19 @@ async fn download_single(
     pb.finish_with_message(format!("{} done", filename));
 
     // 计算哈希
-    let mut file = File::open(&filepath)?;
+    let (sha256, sha1) = hash_file(&filepath)?;
+
+    Ok(FileDownloadResult {
+        url: url.to_string(),
+        path: filepath,
+        sha256,
+        sha1,
+    })
+}
+
+/// 计算文件的哈希值，第一个返回值为 SHA256 第二个为 SHA1
+pub fn hash_file(path: &Path) -> Result<(String, String), Error> {
+    let mut file = File::open(path)?;
     let mut sha256 = Sha256::new();
     let mut sha1 = Sha1::new();
     let mut buf = [0u8; 8192];
@@ -245,11 +273,5 @@ async fn download_single(
         sha256.update(&buf[..n]);
         sha1.update(&buf[..n]);
     }
-
-    Ok(FileDownloadResult {
-        url: url.to_string(),
-        path: filepath,
-        sha256: hex::encode(sha256.finalize()),
-        sha1: hex::encode(sha1.finalize()),
-    })
+    Ok((hex::encode(sha256.finalize()), hex::encode(sha1.finalize())))
 }
@@ -2,7 +2,7 @@ use crate::project_manager::tools::{ServerType, VersionInfo};
 use anyhow::Error;
 use infer;
 use regex::Regex;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Cursor, Read};
 use std::path::Path;
 use tracing::debug;
@@ -90,6 +90,21 @@ pub fn analyze_je_game(jar_path: &Path) -> Result<VersionInfo, Error> {
 
     // 谨慎使用 `?` `unwrap()` `expect()`，避免影响后续判断
 
+    // Fabric 和 Quilt 的启动器获取信息
+    debug!("analyze_je_game:  Read the mod loader launcher");
+    if let Some((server_type, version, _)) = analyze_mod_loader(jar_path, &info.main_class) {
+        return VersionInfo::get_version_info(&version, server_type);
+    }
+
+    // 代理服务端获取信息(读取 Implementation-Version)
+    debug!("analyze_je_game:  Read \"Implementation-Version\" of the proxy");
+    if let Some((server_type, version)) = read_manifest(jar_path)
+        .and_then(|manifest| manifest_value(&manifest, "Implementation-Version"))
+        .and_then(|x| parse_proxy_version(&info.main_class, &x))
+    {
+        return VersionInfo::get_version_info(&version, server_type);
+    }
+
     // 1.18+ 版本获取信息(读取 META-INF/versions.list)
     debug!("analyze_je_game:  Read \"META-INF/versions.list\"");
     // 读取 Jar 文件
@@ -222,6 +237,157 @@ pub fn analyze_je_game(jar_path: &Path) -> Result<VersionInfo, Error> {
     ))
 }
 
+/// 分析模组服务端的启动器，尝试获得模组加载器版本
+pub fn analyze_loader_version(jar_path: &Path) -> Option<String> {
+    let info = analyze_jar(jar_path).ok()?;
+    analyze_mod_loader(jar_path, &info.main_class)?.2
+}
+
+/// 分析 Fabric 和 Quilt 的启动器，返回服务端类型、游戏版本和加载器版本
+fn analyze_mod_loader(
+    jar_path: &Path,
+    main_class: &str,
+) -> Option<(ServerType, String, Option<String>)> {
+    let file = File::open(jar_path).ok()?;
+    let mut archive = ZipArchive::new(&file).ok()?;
+    match main_class {
+        // Fabric Meta 提供的单文件启动器，读取 install.properties
+        "net.fabricmc.installer.ServerLauncher" => {
+            let mut properties = String::new();
+            archive
+                .by_name("install.properties")
+                .ok()?
+                .read_to_string(&mut properties)
+                .ok()?;
+            let value = |key: &str| {
+                properties.lines().find_map(|line| {
+                    line.split_once('=')
+                        .filter(|(k, _)| k.trim() == key)
+                        .map(|(_, v)| v.trim().to_string())
+                })
+            };
+            Some((
+                ServerType::Fabric,
+                value("game-version")?,
+                value("fabric-loader-version"),
+            ))
+        }
+        // 安装器生成的启动器，读取 Class-Path 中的依赖库
+        "net.fabricmc.loader.launch.server.FabricServerLauncher"
+        | "net.fabricmc.loader.impl.launch.server.FabricServerLauncher"
+        | "org.quiltmc.loader.impl.launch.server.QuiltServerLauncher" => {
+            let manifest = read_manifest(jar_path)?;
+            let class_path = manifest_value(&manifest, "Class-Path")?;
+            // 形如 libraries/net/fabricmc/intermediary/1.21.1/intermediary-1.21.1.jar
+            let library_version = |name: &str| {
+                class_path.split_whitespace().find_map(|x| {
+                    let mut parts = x.split('/').skip_while(|part| *part != name);
+                    parts.next()?;
+                    parts.next().map(|v| v.to_string())
+                })
+            };
+            let (server_type, loader) = if main_class.starts_with("org.quiltmc") {
+                (ServerType::Quilt, "quilt-loader")
+            } else {
+                (ServerType::Fabric, "fabric-loader")
+            };
+            Some((
+                server_type,
+                library_version("intermediary")?,
+                library_version(loader),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// 读取 Jar 文件的清单，合并被折行的内容
+fn read_manifest(jar_path: &Path) -> Option<String> {
+    let file = File::open(jar_path).ok()?;
+    let mut archive = ZipArchive::new(&file).ok()?;
+    let mut manifest = String::new();
+    archive
+        .by_name("META-INF/MANIFEST.MF")
+        .ok()?
+        .read_to_string(&mut manifest)
+        .ok()?;
+    Some(manifest.replace("\r\n", "\n").replace("\n ", ""))
+}
+
+/// 获取清单中的值
+fn manifest_value(manifest: &str, key: &str) -> Option<String> {
+    manifest.lines().find_map(|line| {
+        line.split_once(':')
+            .filter(|(k, _)| k.trim() == key)
+            .map(|(_, v)| v.trim().to_string())
+    })
+}
+
+/// 根据主类和 Implementation-Version 识别代理服务端，返回服务端类型和版本
+fn parse_proxy_version(main_class: &str, implementation: &str) -> Option<(ServerType, String)> {
+    match main_class {
+        // 形如 "3.4.0-SNAPSHOT (git-c5a6ff6b-b436)"
+        "com.velocitypowered.proxy.Velocity" => Some((
+            ServerType::Velocity,
+            implementation.split_whitespace().next()?.to_string(),
+        )),
+        // 形如 "git:Waterfall-Bootstrap:1.21-R0.1-SNAPSHOT:d3b5b0e:578"
+        "net.md_5.bungee.Bootstrap" if implementation.contains("Waterfall") => Some((
+            ServerType::Waterfall,
+            implementation
+                .split(':')
+                .nth(2)?
+                .split("-R")
+                .next()?
+                .to_string(),
+        )),
+        // BungeeCord 只有构建号，没有版本
+        "net.md_5.bungee.Bootstrap" => Some((ServerType::BungeeCord, "latest".to_string())),
+        _ => None,
+    }
+}
+
+/// 分析 bedrock_server 文件，尝试获得游戏版本
+pub fn analyze_bds_game(server_path: &Path) -> Result<VersionInfo, Error> {
+    // 读取同目录下的发行说明
+    debug!("analyze_bds_game:  Read \"release-notes.txt\"");
+    let release_notes = server_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("release-notes.txt");
+    if let Ok(notes) = fs::read_to_string(release_notes) {
+        let re = Regex::new(r"\d+\.\d+\.\d+(\.\d+)?")?;
+        if let Some(m) = re.find(&notes) {
+            return VersionInfo::get_version_info(m.as_str(), ServerType::BDS);
+        }
+    }
+
+    // 读取二进制文件中的字符串
+    debug!("analyze_bds_game:  Read strings of the binary");
+    let data = fs::read(server_path)?;
+    match find_bds_version(&data) {
+        Some(version) => VersionInfo::get_version_info(&version, ServerType::BDS),
+        None => Err(Error::msg(
+            "Version parsing failed: Version information cannot be found.",
+        )),
+    }
+}
+
+/// 在二进制数据的可打印字符串中查找形如 1.X.Y.Z 的版本号，取最大者
+fn find_bds_version(data: &[u8]) -> Option<String> {
+    let re = Regex::new(r"^1\.\d{2}\.\d+\.\d+$").ok()?;
+    data.split(|b| !b.is_ascii_graphic())
+        .filter(|s| (7..=16).contains(&s.len()))
+        .filter_map(|s| std::str::from_utf8(s).ok())
+        .filter(|s| re.is_match(s))
+        .max_by_key(|s| {
+            s.split('.')
+                .map(|x| x.parse::<u32>().unwrap_or(0))
+                .collect::<Vec<_>>()
+        })
+        .map(|s| s.to_string())
+}
+
 /// 从 ZipFile 读取 .class 文件并解析字符串常量池
 fn parse_class_strings_from_zip(file: &mut ZipFile<&File>) -> Vec<String> {
     fn read_u1(c: &mut Cursor<Vec<u8>>) -> Option<u8> {
@@ -286,3 +452,67 @@ fn parse_class_strings_from_zip(file: &mut ZipFile<&File>) -> Vec<String> {
 
     strings
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_bds_version() {
+        let data = b"\0\x01zlib 1.2.13\0Version: \x001.21.44.01\0\x7f1.20.81.01\0v1.21.44.01";
+        assert_eq!(find_bds_version(data), Some("1.21.44.01".to_string()));
+        assert_eq!(find_bds_version(b"\0no version\0"), None);
+    }
+
+    #[test]
+    fn test_parse_proxy_version() {
+        assert_eq!(
+            parse_proxy_version(
+                "com.velocitypowered.proxy.Velocity",
+                "3.4.0-SNAPSHOT (git-c5a6ff6b-b436)"
+            ),
+            Some((ServerType::Velocity, "3.4.0-SNAPSHOT".to_string()))
+        );
+        assert_eq!(
+            parse_proxy_version(
+                "net.md_5.bungee.Bootstrap",
+                "git:Waterfall-Bootstrap:1.21-R0.1-SNAPSHOT:d3b5b0e:578"
+            ),
+            Some((ServerType::Waterfall, "1.21".to_string()))
+        );
+        assert_eq!(
+            parse_proxy_version("net.minecraft.server.Main", "1.21.1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_analyze_mod_loader() {
+        use std::io::Write;
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let path = std::env::temp_dir().join(format!(
+            "test-fabric-server-launch-{}.jar",
+            uuid::Uuid::new_v4()
+        ));
+        let mut jar = ZipWriter::new(File::create(&path).unwrap());
+        jar.start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default())
+            .unwrap();
+        jar.write_all(
+            b"Manifest-Version: 1.0\r\nMain-Class: net.fabricmc.loader.impl.launch.server.FabricServerLauncher\r\nClass-Path: libraries/net/fabricmc/fabric-loader/0.16.9/fabric-loader-0.1\r\n 6.9.jar libraries/net/fabricmc/intermediary/1.21.1/intermediary-1.21.1.jar\r\n",
+        )
+        .unwrap();
+        jar.finish().unwrap();
+
+        let result = analyze_mod_loader(
+            &path,
+            "net.fabricmc.loader.impl.launch.server.FabricServerLauncher",
+        );
+        // 先删除文件，断言失败时也不会留下
+        fs::remove_file(&path).unwrap();
+        let (server_type, version, loader) = result.unwrap();
+        assert_eq!(server_type, ServerType::Fabric);
+        assert_eq!(version, "1.21.1");
+        assert_eq!(loader.as_deref(), Some("0.16.9"));
+    }
+}
@@ -14,14 +14,7 @@ use zip::ZipArchive;
 /// 自动管理 Java 的情况下，自动下载 Java
 pub fn prepare_java(edition: JavaType, version: usize) -> Result<(), Error> {
     debug!("Prepare Java");
-    let runtime_path = PathBuf::from(format!(
-        "{}/java-{}-{}-{}-{}",
-        RUNTIME_DIR,
-        version,
-        edition,
-        std::env::consts::OS,
-        std::env::consts::ARCH
-    ));
+    let runtime_path = runtime_path(&edition, version);
 
     if check_java(&runtime_path) {
         return Ok(()); // 已安装可用
@@ -36,14 +29,31 @@ pub fn prepare_java(edition: JavaType, version: usize) -> Result<(), Error> {
     Ok(())
 }
 
-/// 检查 JAVA_HOME 是否可用，通过尝试运行 `java -version`
-pub fn check_java(java_home: &Path) -> bool {
-    debug!("Check Java");
-    let java_bin = if cfg!(windows) {
+/// 自动管理的 Java 的安装目录
+pub fn runtime_path(edition: &JavaType, version: usize) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/java-{}-{}-{}-{}",
+        RUNTIME_DIR,
+        version,
+        edition,
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    ))
+}
+
+/// JAVA_HOME 中 java 可执行文件的路径
+pub fn java_binary(java_home: &Path) -> PathBuf {
+    if cfg!(windows) {
         java_home.join("bin").join("java.exe")
     } else {
         java_home.join("bin").join("java")
-    };
+    }
+}
+
+/// 检查 JAVA_HOME 是否可用，通过尝试运行 `java -version`
+pub fn check_java(java_home: &Path) -> bool {
+    debug!("Check Java");
+    let java_bin = java_binary(java_home);
 
     if !java_bin.exists() {
         return false;
@@ -5,10 +5,21 @@ mod core_manager;
 mod downloader;
 mod file_parser;
 mod java_manager;
+mod plugin_manager;
+mod properties;
+mod provider;
 mod version_parser;
 
-pub use core_manager::{install_bds, install_je};
-pub use downloader::download_files;
-pub use file_parser::{analyze_jar, analyze_je_game, get_mime_type};
-pub use java_manager::{check_java, prepare_java};
-pub use version_parser::{PaperProject, ServerType, VersionInfo, VersionManifest, VersionType};
+pub use core_manager::{BDS_EXECUTABLE, CoreArtifact, extract_bds, print_supported_versions};
+pub use downloader::{FileDownloadResult, download_files, hash_file};
+pub use file_parser::{
+    analyze_bds_game, analyze_jar, analyze_je_game, analyze_loader_version, get_mime_type,
+};
+pub use java_manager::{check_java, java_binary, prepare_java, runtime_path};
+pub use plugin_manager::{
+    PluginArtifact, install_locked_plugin, install_plugin, plugin_is_current, plugin_platform,
+    remove_plugin, resolve_plugin,
+};
+pub use properties::{ServerProperties, property_value, validate_property};
+pub use provider::{LaunchSpec, provider_for};
+pub use version_parser::{ServerType, VersionInfo, VersionManifest, VersionType};
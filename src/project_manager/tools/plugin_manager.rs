@@ -0,0 +1,454 @@
+use crate::project_manager::config::{Plugin, PluginSource};
+use crate::project_manager::lock::LockedPlugin;
+use crate::project_manager::tools::ServerType;
+use crate::project_manager::tools::downloader::download_file_single_thread;
+use crate::project_manager::tools::hash_file;
+use crate::project_manager::{CACHE_DIR, HANGAR_API, MODRINTH_API, PLUGIN_DIR, USER_AGENT};
+use anyhow::Error;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+
+/// 解析完成、等待安装的插件文件
+#[derive(Debug, Clone)]
+pub struct PluginArtifact {
+    /// 插件名称
+    pub name: String,
+    /// 解析得到的具体版本
+    pub version: String,
+    /// 下载链接
+    pub url: String,
+    /// 安装到插件目录的文件名
+    pub file: String,
+    /// 来源提供的 SHA1
+    pub sha1: Option<String>,
+    /// 来源提供的 SHA256
+    pub sha256: Option<String>,
+}
+
+/// 服务端对应的 Modrinth loader 列表和 Hangar 平台，不支持插件时返回 None
+pub fn plugin_platform(
+    server_type: &ServerType,
+) -> Option<(&'static [&'static str], &'static str)> {
+    match server_type {
+        ServerType::Paper | ServerType::Leaves => Some((&["paper", "spigot", "bukkit"], "PAPER")),
+        ServerType::Folia => Some((&["folia"], "PAPER")),
+        ServerType::Purpur => Some((&["purpur", "paper", "spigot", "bukkit"], "PAPER")),
+        ServerType::Velocity => Some((&["velocity"], "VELOCITY")),
+        ServerType::Waterfall => Some((&["waterfall", "bungeecord"], "WATERFALL")),
+        ServerType::BungeeCord => Some((&["bungeecord"], "WATERFALL")),
+        _ => None,
+    }
+}
+
+/// 根据配置解析插件的具体版本和下载地址
+pub fn resolve_plugin(
+    plugin: &Plugin,
+    server_type: &ServerType,
+    game_version: &str,
+) -> Result<PluginArtifact, Error> {
+    debug!("Resolve plugin {}", plugin.name);
+    match plugin.source {
+        PluginSource::Modrinth => resolve_modrinth(plugin, server_type, game_version),
+        PluginSource::Hangar => resolve_hangar(plugin, server_type, game_version),
+        PluginSource::Url => {
+            let url = plugin.url.clone().ok_or(Error::msg(format!(
+                "The plugin {} has no URL configured",
+                plugin.name
+            )))?;
+            let file = file_name_from_url(&url)
+                .ok_or(Error::msg(format!("Invalid plugin URL: {}", url)))?;
+            Ok(PluginArtifact {
+                name: plugin.name.clone(),
+                version: plugin.version.clone(),
+                url,
+                file,
+                sha1: None,
+                sha256: None,
+            })
+        }
+    }
+}
+
+/// 用于解析 Modrinth API Version 的 JSON
+#[derive(Debug, Deserialize)]
+struct ModrinthVersion {
+    version_number: String,
+    version_type: String,
+    files: Vec<ModrinthFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthFile {
+    url: String,
+    filename: String,
+    primary: bool,
+    hashes: ModrinthHashes,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModrinthHashes {
+    sha1: String,
+}
+
+/// 从 Modrinth 解析插件
+fn resolve_modrinth(
+    plugin: &Plugin,
+    server_type: &ServerType,
+    game_version: &str,
+) -> Result<PluginArtifact, Error> {
+    let (loaders, _) = plugin_platform(server_type)
+        .ok_or(Error::msg("This server type does not support plugins"))?;
+    let mut request = reqwest::blocking::Client::new()
+        .get(format!("{}/project/{}/version", MODRINTH_API, plugin.name))
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .query(&[("loaders", serde_json::to_string(loaders)?)]);
+    // 代理服务端的版本与游戏版本无关，不按游戏版本筛选
+    if !server_type.is_proxy() {
+        request = request.query(&[("game_versions", serde_json::to_string(&[game_version])?)]);
+    }
+    let response = request.send()?;
+    if !response.status().is_success() {
+        return Err(Error::msg(format!(
+            "Request failed for {}: {}",
+            plugin.name,
+            response.status()
+        )));
+    }
+    // Modrinth 按发布时间倒序返回
+    let versions = response.json::<Vec<ModrinthVersion>>()?;
+    let version = versions
+        .into_iter()
+        .find(|v| {
+            match_version(&plugin.version, &v.version_number)
+                && (!is_latest(&plugin.version) || v.version_type == "release")
+        })
+        .ok_or(Error::msg(format!(
+            "No version of {} matches \"{}\" for {}",
+            plugin.name, plugin.version, game_version
+        )))?;
+    let file = version
+        .files
+        .iter()
+        .find(|f| f.primary)
+        .or(version.files.first())
+        .ok_or(Error::msg(format!("{} has no files", plugin.name)))?;
+    Ok(PluginArtifact {
+        name: plugin.name.clone(),
+        version: version.version_number.clone(),
+        url: file.url.clone(),
+        file: file.filename.clone(),
+        sha1: Some(file.hashes.sha1.clone()),
+        sha256: None,
+    })
+}
+
+/// 用于解析 Hangar API Versions 的 JSON
+#[derive(Debug, Deserialize)]
+struct HangarVersions {
+    result: Vec<HangarVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HangarVersion {
+    name: String,
+    channel: HangarChannel,
+    downloads: HashMap<String, HangarDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HangarChannel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HangarDownload {
+    file_info: Option<HangarFileInfo>,
+    external_url: Option<String>,
+    download_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HangarFileInfo {
+    name: String,
+    sha256_hash: String,
+}
+
+/// 从 Hangar 解析插件
+fn resolve_hangar(
+    plugin: &Plugin,
+    server_type: &ServerType,
+    game_version: &str,
+) -> Result<PluginArtifact, Error> {
+    let (_, platform) = plugin_platform(server_type)
+        .ok_or(Error::msg("This server type does not support plugins"))?;
+    let mut request = reqwest::blocking::Client::new()
+        .get(format!("{}/projects/{}/versions", HANGAR_API, plugin.name))
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .query(&[("limit", "25"), ("offset", "0"), ("platform", platform)]);
+    // 代理服务端的版本与游戏版本无关，不按游戏版本筛选
+    if !server_type.is_proxy() {
+        request = request.query(&[("platformVersion", game_version)]);
+    }
+    let response = request.send()?;
+    if !response.status().is_success() {
+        return Err(Error::msg(format!(
+            "Request failed for {}: {}",
+            plugin.name,
+            response.status()
+        )));
+    }
+    // Hangar 按发布时间倒序返回
+    let versions = response.json::<HangarVersions>()?.result;
+    let (version, download) = versions
+        .iter()
+        .filter(|v| {
+            match_version(&plugin.version, &v.name)
+                && (!is_latest(&plugin.version) || v.channel.name == "Release")
+        })
+        .find_map(|v| v.downloads.get(platform).map(|d| (v, d)))
+        .ok_or(Error::msg(format!(
+            "No version of {} matches \"{}\" for {}",
+            plugin.name, plugin.version, game_version
+        )))?;
+    let url = download
+        .download_url
+        .clone()
+        .or(download.external_url.clone())
+        .ok_or(Error::msg(format!("{} has no download link", plugin.name)))?;
+    let file = match &download.file_info {
+        Some(info) => info.name.clone(),
+        None => {
+            file_name_from_url(&url).ok_or(Error::msg(format!("Invalid plugin URL: {}", url)))?
+        }
+    };
+    Ok(PluginArtifact {
+        name: plugin.name.clone(),
+        version: version.name.clone(),
+        url,
+        file,
+        sha1: None,
+        sha256: download.file_info.as_ref().map(|i| i.sha256_hash.clone()),
+    })
+}
+
+/// 下载、校验并安装插件，`checksum` 为配置文件中指定的校验值
+pub fn install_plugin(
+    artifact: &PluginArtifact,
+    checksum: Option<&str>,
+) -> Result<LockedPlugin, Error> {
+    debug!("Install plugin {}", artifact.name);
+    // 文件名来自远程 API，安装前确认不会写到插件目录之外
+    let name = plugin_file_name(&artifact.file)?;
+    // 下载文件，长度未知，使用单线程下载
+    let file = download_file_single_thread(
+        artifact.url.as_str(),
+        format!("{}/download", CACHE_DIR).as_str(),
+    )?;
+    // 校验文件，失败时删除下载的文件
+    let verified = if artifact.sha1.as_ref().is_some_and(|v| v != &file.sha1) {
+        Err(Error::msg(format!(
+            "SHA1 verification failed: {}",
+            artifact.name
+        )))
+    } else if artifact.sha256.as_ref().is_some_and(|v| v != &file.sha256) {
+        Err(Error::msg(format!(
+            "SHA256 verification failed: {}",
+            artifact.name
+        )))
+    } else if let Some(checksum) = checksum {
+        verify_checksum(checksum, &file.sha1, &file.sha256)
+            .map_err(|e| Error::msg(format!("{}: {}", artifact.name, e)))
+    } else {
+        Ok(())
+    };
+    if let Err(e) = verified {
+        fs::remove_file(&file.path).ok();
+        return Err(e);
+    }
+    // 安装文件
+    fs::create_dir_all(PLUGIN_DIR)?;
+    fs::rename(&file.path, Path::new(PLUGIN_DIR).join(&name))?;
+    Ok(LockedPlugin {
+        name: artifact.name.clone(),
+        version: artifact.version.clone(),
+        url: artifact.url.clone(),
+        file: name,
+        sha256: file.sha256,
+        sha1: file.sha1,
+    })
+}
+
+/// 按照锁文件安装插件，文件已存在且一致时跳过
+pub fn install_locked_plugin(locked: &LockedPlugin) -> Result<(), Error> {
+    let path = Path::new(PLUGIN_DIR).join(&locked.file);
+    if hash_file(&path).is_ok_and(|(sha256, _)| sha256 == locked.sha256) {
+        return Ok(());
+    }
+    install_plugin(
+        &PluginArtifact {
+            name: locked.name.clone(),
+            version: locked.version.clone(),
+            url: locked.url.clone(),
+            file: locked.file.clone(),
+            sha1: Some(locked.sha1.clone()),
+            sha256: Some(locked.sha256.clone()),
+        },
+        None,
+    )?;
+    Ok(())
+}
+
+/// 判断已安装的插件是否与解析结果一致，比较版本、下载链接、校验值以及插件目录中的文件
+pub fn plugin_is_current(
+    installed: &LockedPlugin,
+    artifact: &PluginArtifact,
+    checksum: Option<&str>,
+) -> bool {
+    // URL 来源的版本号由用户填写，修改链接或校验值时版本号不一定变化
+    let matches = installed.version == artifact.version
+        && installed.url == artifact.url
+        && artifact.sha1.as_ref().is_none_or(|x| x == &installed.sha1)
+        && artifact
+            .sha256
+            .as_ref()
+            .is_none_or(|x| x == &installed.sha256)
+        && checksum.is_none_or(|x| verify_checksum(x, &installed.sha1, &installed.sha256).is_ok());
+    // 文件可能在安装后被替换
+    matches
+        && hash_file(&Path::new(PLUGIN_DIR).join(&installed.file))
+            .is_ok_and(|(sha256, sha1)| sha256 == installed.sha256 && sha1 == installed.sha1)
+}
+
+/// 删除已安装的插件文件
+pub fn remove_plugin(installed: &LockedPlugin) -> Result<(), Error> {
+    let path = Path::new(PLUGIN_DIR).join(&installed.file);
+    if path.is_file() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// 是否为“最新版本”约束
+fn is_latest(constraint: &str) -> bool {
+    matches!(constraint.trim(), "" | "*" | "latest")
+}
+
+/// 判断版本是否满足约束，支持 `latest`、`*`、精确版本以及 `x.y.*` 形式的通配
+fn match_version(constraint: &str, version: &str) -> bool {
+    let constraint = constraint.trim();
+    if is_latest(constraint) {
+        return true;
+    }
+    match constraint.strip_suffix('*') {
+        Some(prefix) => version.starts_with(prefix),
+        None => constraint == version,
+    }
+}
+
+/// 校验配置中的校验值，格式为 `sha256:<hex>`、`sha1:<hex>` 或根据长度判断的裸值
+fn verify_checksum(expected: &str, sha1: &str, sha256: &str) -> Result<(), Error> {
+    let expected = expected.trim().to_lowercase();
+    let (algorithm, value) = match expected.split_once(':') {
+        Some((algorithm, value)) => (algorithm.to_string(), value.to_string()),
+        None if expected.len() == 40 => ("sha1".to_string(), expected.clone()),
+        None => ("sha256".to_string(), expected.clone()),
+    };
+    let actual = match algorithm.as_str() {
+        "sha1" => sha1,
+        "sha256" => sha256,
+        _ => {
+            return Err(Error::msg(format!(
+                "Unsupported checksum algorithm: {}",
+                algorithm
+            )));
+        }
+    };
+    if actual == value {
+        Ok(())
+    } else {
+        Err(Error::msg(format!(
+            "{} verification failed",
+            algorithm.to_uppercase()
+        )))
+    }
+}
+
+/// 检查插件的文件名，只保留最后一级，拒绝空名称、`..` 以及包含路径分隔符的名称
+fn plugin_file_name(name: &str) -> Result<String, Error> {
+    let invalid = || Error::msg(format!("Invalid plugin file name: {:?}", name));
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(invalid());
+    }
+    let file_name = Path::new(name)
+        .file_name()
+        .and_then(|x| x.to_str())
+        .ok_or_else(invalid)?;
+    if file_name != name || file_name == ".." {
+        return Err(invalid());
+    }
+    Ok(file_name.to_string())
+}
+
+/// 从 URL 获取文件名，忽略查询参数
+fn file_name_from_url(url: &str) -> Option<String> {
+    url.split(['?', '#'])
+        .next()?
+        .rsplit('/')
+        .next()
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_version() {
+        assert!(match_version("latest", "5.1.0"));
+        assert!(match_version("*", "5.1.0"));
+        assert!(match_version("5.1.*", "5.1.3"));
+        assert!(!match_version("5.1.*", "5.2.0"));
+        assert!(match_version("5.1.0", "5.1.0"));
+        assert!(!match_version("5.1.0", "5.1.0-SNAPSHOT"));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let sha1 = "a".repeat(40);
+        let sha256 = "b".repeat(64);
+        assert!(verify_checksum(&format!("sha1:{}", sha1), &sha1, &sha256).is_ok());
+        assert!(verify_checksum(&sha256.to_uppercase(), &sha1, &sha256).is_ok());
+        assert!(verify_checksum(&sha1, &sha1, &sha256).is_ok());
+        assert!(verify_checksum("sha256:00", &sha1, &sha256).is_err());
+        assert!(verify_checksum("md5:00", &sha1, &sha256).is_err());
+    }
+
+    #[test]
+    fn test_plugin_file_name() {
+        assert_eq!(
+            plugin_file_name("Plugin-1.0.jar").unwrap(),
+            "Plugin-1.0.jar"
+        );
+        assert!(plugin_file_name("").is_err());
+        assert!(plugin_file_name("..").is_err());
+        assert!(plugin_file_name(".").is_err());
+        assert!(plugin_file_name("../Plugin.jar").is_err());
+        assert!(plugin_file_name("/etc/passwd").is_err());
+        assert!(plugin_file_name("..\\Plugin.jar").is_err());
+    }
+
+    #[test]
+    fn test_file_name_from_url() {
+        assert_eq!(
+            file_name_from_url("https://example.com/a/Plugin-1.0.jar?token=1"),
+            Some("Plugin-1.0.jar".to_string())
+        );
+        assert_eq!(file_name_from_url("https://example.com/"), None);
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::Error;
+use std::fs;
+use std::path::Path;
+
+/// `server.properties` 文件，修改时保留注释、空行和未修改的配置项
+#[derive(Debug, Default)]
+pub struct ServerProperties {
+    lines: Vec<Line>,
+    /// Bedrock Edition 的文件不使用转义
+    bedrock: bool,
+}
+
+/// 文件中的一行
+#[derive(Debug)]
+enum Line {
+    /// 注释、空行等原样保留的内容
+    Raw(String),
+    /// 配置项，保存未转义的值
+    Entry { key: String, value: String },
+}
+
+impl ServerProperties {
+    /// 解析文件内容
+    pub fn parse(content: &str, bedrock: bool) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                    return Line::Raw(line.to_string());
+                }
+                match trimmed.split_once(['=', ':']) {
+                    Some((key, value)) => Line::Entry {
+                        key: key.trim().to_string(),
+                        value: if bedrock {
+                            value.trim().to_string()
+                        } else {
+                            unescape(value.trim_start())
+                        },
+                    },
+                    None => Line::Entry {
+                        key: trimmed.trim().to_string(),
+                        value: String::new(),
+                    },
+                }
+            })
+            .collect();
+        ServerProperties { lines, bedrock }
+    }
+
+    /// 读取文件，不存在时返回空的文件
+    pub fn load(path: &Path, bedrock: bool) -> Result<Self, Error> {
+        if !path.is_file() {
+            return Ok(ServerProperties {
+                lines: vec![],
+                bedrock,
+            });
+        }
+        Ok(Self::parse(&fs::read_to_string(path)?, bedrock))
+    }
+
+    /// 写入文件
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// 获取配置项的值
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Entry { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// 设置配置项的值，不存在时添加到文件末尾
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in &mut self.lines {
+            if let Line::Entry { key: k, value: v } = line
+                && k == key
+            {
+                *v = value.to_string();
+                return;
+            }
+        }
+        self.lines.push(Line::Entry {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    }
+}
+
+impl std::fmt::Display for ServerProperties {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Raw(raw) => writeln!(f, "{}", raw)?,
+                Line::Entry { key, value } if self.bedrock => writeln!(f, "{}={}", key, value)?,
+                Line::Entry { key, value } => writeln!(f, "{}={}", key, escape(value))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 按照 Java Properties 的规则反转义
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\x0c'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    Some(c) => result.push(c),
+                    None => result.push_str(&code),
+                }
+            }
+            Some(c) => result.push(c),
+            None => (),
+        }
+    }
+    result
+}
+
+/// 按照 Java Properties 的规则转义，非 ASCII 字符保持原样
+fn escape(value: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\\' | '=' | ':' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            // 开头的空格会被忽略
+            ' ' if i == 0 => result.push_str("\\ "),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\x0c' => result.push_str("\\f"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// 将配置文件中的值转换为 `server.properties` 中的值
+pub fn property_value(value: &toml::Value) -> Result<String, Error> {
+    match value {
+        toml::Value::String(v) => Ok(v.clone()),
+        toml::Value::Integer(v) => Ok(v.to_string()),
+        toml::Value::Float(v) => Ok(v.to_string()),
+        toml::Value::Boolean(v) => Ok(v.to_string()),
+        _ => Err(Error::msg(format!("Unsupported property value: {}", value))),
+    }
+}
+
+/// 检查已知配置项的值，未知的配置项不检查
+pub fn validate_property(bedrock: bool, key: &str, value: &str) -> Result<(), Error> {
+    let range = |min: i64, max: i64| match value.parse::<i64>() {
+        Ok(v) if (min..=max).contains(&v) => Ok(()),
+        _ => Err(Error::msg(format!(
+            "{} must be an integer between {} and {}, got {:?}",
+            key, min, max, value
+        ))),
+    };
+    let one_of = |values: &[&str]| {
+        if values.contains(&value) {
+            Ok(())
+        } else {
+            Err(Error::msg(format!(
+                "{} must be one of {}, got {:?}",
+                key,
+                values.join(", "),
+                value
+            )))
+        }
+    };
+    match key {
+        "server-port" | "server-portv6" | "query.port" | "rcon.port" => range(1, 65535),
+        "gamemode" if bedrock => one_of(&["survival", "creative", "adventure"]),
+        "gamemode" => one_of(&["survival", "creative", "adventure", "spectator"]),
+        "difficulty" => one_of(&["peaceful", "easy", "normal", "hard"]),
+        "view-distance" if bedrock => range(5, i64::MAX),
+        "view-distance" | "simulation-distance" => range(2, 32),
+        "tick-distance" if bedrock => range(4, 12),
+        "max-players" => range(1, i32::MAX as i64),
+        "online-mode" | "pvp" | "white-list" | "allow-list" | "hardcore" | "allow-cheats" => {
+            one_of(&["true", "false"])
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_properties() {
+        let content = "#Minecraft server properties\n#Thu Jan 01 00:00:00 UTC 2025\nmotd=A Minecraft Server\\: test\nserver-port=25565\n\nlevel-seed=\n";
+        let mut properties = ServerProperties::parse(content, false);
+        assert_eq!(properties.get("motd"), Some("A Minecraft Server: test"));
+        assert_eq!(properties.get("level-seed"), Some(""));
+        properties.set("server-port", "25566");
+        properties.set("view-distance", "12");
+        assert_eq!(
+            properties.to_string(),
+            "#Minecraft server properties\n#Thu Jan 01 00:00:00 UTC 2025\nmotd=A Minecraft Server\\: test\nserver-port=25566\n\nlevel-seed=\nview-distance=12\n"
+        );
+    }
+
+    #[test]
+    fn test_validate_property() {
+        assert!(validate_property(false, "server-port", "25565").is_ok());
+        assert!(validate_property(false, "server-port", "70000").is_err());
+        assert!(validate_property(false, "gamemode", "spectator").is_ok());
+        assert!(validate_property(true, "gamemode", "spectator").is_err());
+        assert!(validate_property(false, "view-distance", "far").is_err());
+        assert!(validate_property(false, "motd", "anything").is_ok());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::project_manager::Config;
+use crate::project_manager::config::{BuildChannel, ChecksumType, ProviderTemplate};
+use crate::project_manager::tools::core_manager::{
+    CoreArtifact, fetch_json, install_bds, install_core, latest_bds_version, latest_je_version,
+    print_supported_versions, resolve_bds, resolve_je, supported_je_versions, version_key,
+};
+use crate::project_manager::tools::{
+    FileDownloadResult, ServerType, VersionInfo, analyze_jar, get_mime_type,
+};
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tracing::info;
+
+/// 服务端的启动方式
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchSpec {
+    /// 使用 `java -jar` 启动
+    #[default]
+    Jar,
+    /// 使用 Java 参数文件启动
+    ArgFile,
+    /// 直接运行可执行文件
+    Native,
+}
+
+/// 服务端来源，负责列出版本、解析构建、下载安装以及提供启动方式
+pub trait ServerProvider {
+    /// 获取支持的游戏版本，无法列出时返回 None
+    fn versions(&self) -> Result<Option<Vec<String>>, Error>;
+
+    /// 获取最新的游戏版本，`channel` 为可接受的最低构建通道
+    fn latest_version(&self, channel: Option<&BuildChannel>) -> Result<String, Error>;
+
+    /// 检查游戏版本并获取版本信息
+    fn version_info(&self, version: &str) -> Result<VersionInfo, Error>;
+
+    /// 解析需要安装的构建，`build` 和 `loader` 为锁定的构建号和模组加载器版本
+    fn resolve(
+        &self,
+        version_info: &VersionInfo,
+        build: Option<usize>,
+        loader: Option<&str>,
+        channel: Option<&BuildChannel>,
+    ) -> Result<CoreArtifact, Error>;
+
+    /// 下载、校验并安装服务端，`path` 为服务端的可执行文件
+    fn install(&self, artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error>;
+
+    /// 检查服务端文件是否可用
+    fn check(&self, path: &Path) -> bool;
+
+    /// 服务端的启动方式
+    fn launch(&self) -> LaunchSpec;
+}
+
+/// 获取实例使用的服务端来源，`Other` 类型配置了下载模板时使用模板
+pub fn provider_for(config: &Config) -> Box<dyn ServerProvider> {
+    match (&config.project.server_type, &config.provider) {
+        (ServerType::BDS, _) => Box::new(BedrockProvider),
+        (ServerType::Other, Some(template)) => Box::new(TemplateProvider {
+            template: template.clone(),
+        }),
+        (server_type @ (ServerType::Forge | ServerType::NeoForge), _) => Box::new(JavaProvider {
+            server_type: server_type.clone(),
+            launch: LaunchSpec::ArgFile,
+        }),
+        (server_type, _) => Box::new(JavaProvider {
+            server_type: server_type.clone(),
+            launch: LaunchSpec::Jar,
+        }),
+    }
+}
+
+/// PacMine 内置支持的 Java 版服务端
+struct JavaProvider {
+    server_type: ServerType,
+    launch: LaunchSpec,
+}
+
+impl ServerProvider for JavaProvider {
+    fn versions(&self) -> Result<Option<Vec<String>>, Error> {
+        supported_je_versions(&self.server_type)
+    }
+
+    fn latest_version(&self, channel: Option<&BuildChannel>) -> Result<String, Error> {
+        latest_je_version(&self.server_type, channel)
+    }
+
+    fn version_info(&self, version: &str) -> Result<VersionInfo, Error> {
+        VersionInfo::get_version_info(version, self.server_type.clone())
+    }
+
+    fn resolve(
+        &self,
+        version_info: &VersionInfo,
+        build: Option<usize>,
+        loader: Option<&str>,
+        channel: Option<&BuildChannel>,
+    ) -> Result<CoreArtifact, Error> {
+        resolve_je(version_info, build, loader, channel)
+    }
+
+    fn install(&self, artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+        install_core(artifact, path)
+    }
+
+    fn check(&self, path: &Path) -> bool {
+        match self.launch {
+            LaunchSpec::Jar => analyze_jar(path).is_ok(),
+            // 参数文件启动的服务端由安装器生成，只检查文件是否存在
+            _ => path.is_file(),
+        }
+    }
+
+    fn launch(&self) -> LaunchSpec {
+        self.launch.clone()
+    }
+}
+
+/// Bedrock Edition 服务端
+struct BedrockProvider;
+
+impl ServerProvider for BedrockProvider {
+    fn versions(&self) -> Result<Option<Vec<String>>, Error> {
+        Ok(None)
+    }
+
+    fn latest_version(&self, _channel: Option<&BuildChannel>) -> Result<String, Error> {
+        latest_bds_version()
+    }
+
+    fn version_info(&self, version: &str) -> Result<VersionInfo, Error> {
+        VersionInfo::get_version_info(version, ServerType::BDS)
+    }
+
+    fn resolve(
+        &self,
+        version_info: &VersionInfo,
+        _build: Option<usize>,
+        _loader: Option<&str>,
+        _channel: Option<&BuildChannel>,
+    ) -> Result<CoreArtifact, Error> {
+        resolve_bds(&version_info.name)
+    }
+
+    fn install(&self, artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+        install_bds(artifact, path)
+    }
+
+    /// 检查可执行文件与当前平台是否匹配
+    fn check(&self, path: &Path) -> bool {
+        match std::env::consts::OS {
+            "linux" => get_mime_type(path) == "application/x-executable",
+            "windows" => get_mime_type(path) == "application/vnd.microsoft.portable-executable",
+            _ => false,
+        }
+    }
+
+    fn launch(&self) -> LaunchSpec {
+        LaunchSpec::Native
+    }
+}
+
+/// 使用配置文件中的下载模板安装的服务端
+struct TemplateProvider {
+    template: ProviderTemplate,
+}
+
+impl TemplateProvider {
+    /// 替换链接中的占位符，链接需要的值缺省时返回错误
+    fn format_url(
+        &self,
+        url: &str,
+        version: &str,
+        build: Option<usize>,
+        loader: Option<&str>,
+    ) -> Result<String, Error> {
+        let mut url = url.replace("{version}", version);
+        if url.contains("{build}") {
+            let build = build.ok_or(Error::msg(format!(
+                "{} requires a build number, set `builds_url` in [provider] or `build` in [project]",
+                self.template.name
+            )))?;
+            url = url.replace("{build}", &build.to_string());
+        }
+        if url.contains("{loader}") {
+            let loader = loader.ok_or(Error::msg(format!(
+                "{} requires `loader_version` in [project]",
+                self.template.name
+            )))?;
+            url = url.replace("{loader}", loader);
+        }
+        Ok(url)
+    }
+
+    /// 获取构建列表，返回构建号和对应的构建
+    fn builds(&self, url: &str) -> Result<Vec<(usize, Value)>, Error> {
+        let response = fetch_json::<Value>(url)?;
+        let builds = response
+            .pointer(&self.template.builds_pointer)
+            .and_then(|x| x.as_array())
+            .ok_or(Error::msg("The build list cannot be found in the response"))?;
+        Ok(builds
+            .iter()
+            .filter_map(|x| {
+                let id = x.pointer(&self.template.build_pointer)?;
+                // 构建号可能以字符串表示
+                let id = id
+                    .as_u64()
+                    .map(|x| x as usize)
+                    .or(id.as_str().and_then(|x| x.parse().ok()))?;
+                Some((id, x.clone()))
+            })
+            .collect())
+    }
+}
+
+impl ServerProvider for TemplateProvider {
+    fn versions(&self) -> Result<Option<Vec<String>>, Error> {
+        let Some(url) = &self.template.versions_url else {
+            return Ok(None);
+        };
+        let response = fetch_json::<Value>(url)?;
+        let versions = response
+            .pointer(&self.template.versions_pointer)
+            .and_then(|x| x.as_array())
+            .ok_or(Error::msg(
+                "The version list cannot be found in the response",
+            ))?;
+        Ok(Some(
+            versions
+                .iter()
+                .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                .collect(),
+        ))
+    }
+
+    fn latest_version(&self, _channel: Option<&BuildChannel>) -> Result<String, Error> {
+        let versions = self.versions()?.ok_or(Error::msg(
+            "The latest version cannot be determined, set `versions_url` in [provider]",
+        ))?;
+        // 优先使用不含预发布后缀的版本
+        versions
+            .iter()
+            .filter(|x| !x.contains('-'))
+            .max_by_key(|x| version_key(x))
+            .or(versions.iter().max_by_key(|x| version_key(x)))
+            .cloned()
+            .ok_or(Error::msg("Failed to get the latest version"))
+    }
+
+    fn version_info(&self, version: &str) -> Result<VersionInfo, Error> {
+        if let Some(versions) = self.versions()?
+            && !versions.iter().any(|x| x == version)
+        {
+            print_supported_versions(&versions);
+            return Err(Error::msg(format!(
+                "{} version does not exist.",
+                self.template.name
+            )));
+        }
+        VersionInfo::get_version_info(version, ServerType::Other)
+    }
+
+    fn resolve(
+        &self,
+        version_info: &VersionInfo,
+        build: Option<usize>,
+        loader: Option<&str>,
+        _channel: Option<&BuildChannel>,
+    ) -> Result<CoreArtifact, Error> {
+        let version = version_info.name.clone();
+        let selected =
+            match &self.template.builds_url {
+                Some(url) => {
+                    let builds = self.builds(&self.format_url(url, &version, None, loader)?)?;
+                    let selected =
+                        match build {
+                            // 使用指定的构建
+                            Some(build) => {
+                                builds.into_iter().find(|x| x.0 == build).ok_or(Error::msg(
+                                    format!("Build {} of {} does not exist", build, version),
+                                ))?
+                            }
+                            // 使用最新的构建
+                            None => {
+                                let selected = builds.into_iter().max_by_key(|x| x.0).ok_or(
+                                    Error::msg(format!("No build of {} is available", version)),
+                                )?;
+                                info!("Use the latest build {} of {}", selected.0, version);
+                                selected
+                            }
+                        };
+                    Some(selected)
+                }
+                None => None,
+            };
+        let build = selected.as_ref().map(|x| x.0).or(build);
+        let checksum = match (&self.template.checksum_pointer, &selected) {
+            (Some(pointer), Some((_, value))) => value
+                .pointer(pointer)
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_lowercase()),
+            _ => None,
+        };
+        let url = self.format_url(&self.template.download_url, &version, build, loader)?;
+        Ok(CoreArtifact {
+            server_type: ServerType::Other,
+            version,
+            build,
+            // 仅记录链接中使用的加载器版本
+            loader: if self.template.download_url.contains("{loader}") {
+                loader.map(|x| x.to_string())
+            } else {
+                None
+            },
+            url,
+            md5: checksum
+                .clone()
+                .filter(|_| self.template.checksum_type == ChecksumType::Md5),
+            sha1: checksum
+                .clone()
+                .filter(|_| self.template.checksum_type == ChecksumType::Sha1),
+            sha256: checksum.filter(|_| self.template.checksum_type == ChecksumType::Sha256),
+            changelog: vec![],
+        })
+    }
+
+    fn install(&self, artifact: &CoreArtifact, path: &Path) -> Result<FileDownloadResult, Error> {
+        let file = install_core(artifact, path)?;
+        // 直接运行的服务端需要执行权限
+        #[cfg(target_family = "unix")]
+        if self.template.launch == LaunchSpec::Native {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(file)
+    }
+
+    fn check(&self, path: &Path) -> bool {
+        match self.template.launch {
+            LaunchSpec::Jar => analyze_jar(path).is_ok(),
+            _ => path.is_file(),
+        }
+    }
+
+    fn launch(&self) -> LaunchSpec {
+        self.template.launch.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_provider() {
+        let template: ProviderTemplate = toml::from_str(
+            r#"
+            name = "Mohist"
+            builds_url = "https://example.com/{version}/builds"
+            builds_pointer = "/builds"
+            build_pointer = "/number"
+            checksum_pointer = "/fileMd5"
+            checksum_type = "md5"
+            download_url = "https://example.com/{version}/builds/{build}/download"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(template.launch, LaunchSpec::Jar);
+        let provider = TemplateProvider { template };
+        assert_eq!(
+            provider
+                .format_url(&provider.template.download_url, "1.20.1", Some(910), None)
+                .unwrap(),
+            "https://example.com/1.20.1/builds/910/download"
+        );
+        assert!(
+            provider
+                .format_url(&provider.template.download_url, "1.20.1", None, None)
+                .is_err()
+        );
+    }
+}
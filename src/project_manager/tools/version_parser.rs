@@ -3,6 +3,7 @@ use anyhow::Error;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use tracing::error;
 
 /// 可选的服务端类型
@@ -21,10 +22,91 @@ pub enum ServerType {
     Leaves,
     /// PurpurMC 服务端
     Purpur,
-    /// 自定义服务端，不支持更新功能以及插件管理
+    /// Fabric 模组服务端
+    Fabric,
+    /// Quilt 模组服务端
+    Quilt,
+    /// Forge 模组服务端
+    Forge,
+    /// NeoForge 模组服务端
+    NeoForge,
+    /// PaperMC 的代理服务端
+    Velocity,
+    /// PaperMC 维护的 BungeeCord 分支
+    Waterfall,
+    /// SpigotMC 的代理服务端
+    BungeeCord,
+    /// 自定义服务端，配置 `[provider]` 后支持安装和更新，不支持插件管理
     Other,
 }
 
+impl FromStr for ServerType {
+    type Err = Error;
+
+    /// 不区分大小写地解析服务端类型
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_lowercase().as_str() {
+            "vanilla" => Ok(ServerType::Vanilla),
+            "bds" | "bedrock" => Ok(ServerType::BDS),
+            "paper" => Ok(ServerType::Paper),
+            "folia" => Ok(ServerType::Folia),
+            "leaves" => Ok(ServerType::Leaves),
+            "purpur" => Ok(ServerType::Purpur),
+            "fabric" => Ok(ServerType::Fabric),
+            "quilt" => Ok(ServerType::Quilt),
+            "forge" => Ok(ServerType::Forge),
+            "neoforge" => Ok(ServerType::NeoForge),
+            "velocity" => Ok(ServerType::Velocity),
+            "waterfall" => Ok(ServerType::Waterfall),
+            "bungeecord" => Ok(ServerType::BungeeCord),
+            "other" => Ok(ServerType::Other),
+            _ => Err(Error::msg(format!("Unknown server type: {}", s))),
+        }
+    }
+}
+
+impl ServerType {
+    /// 是否为代理服务端，代理服务端的版本与游戏版本无关
+    pub fn is_proxy(&self) -> bool {
+        matches!(
+            self,
+            ServerType::Velocity | ServerType::Waterfall | ServerType::BungeeCord
+        )
+    }
+
+    /// 关闭服务端使用的命令
+    pub fn stop_command(&self) -> &'static str {
+        if self.is_proxy() { "end" } else { "stop" }
+    }
+
+    /// 发送停止命令后默认等待退出的时间，单位秒，保存大型世界可能需要较长时间
+    pub fn stop_timeout(&self) -> u64 {
+        match self {
+            _ if self.is_proxy() => 10,
+            ServerType::BDS => 30,
+            _ => 60,
+        }
+    }
+
+    /// 向所有玩家广播消息的命令，Velocity 没有内置的广播命令
+    pub fn broadcast_command(&self, message: &str) -> Option<String> {
+        match self {
+            ServerType::Velocity => None,
+            ServerType::Waterfall | ServerType::BungeeCord => Some(format!("alert {}", message)),
+            _ => Some(format!("say {}", message)),
+        }
+    }
+
+    /// 存档所在的目录，BDS 的每个世界都在 `worlds` 下的子目录中
+    pub fn world_dir(&self) -> &'static str {
+        if *self == ServerType::BDS {
+            "worlds"
+        } else {
+            "world"
+        }
+    }
+}
+
 /// 服务端版本类型
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)] // 添加 Clone 和 PartialEq
 #[serde(rename_all = "snake_case")]
@@ -79,6 +161,14 @@ pub struct ManifestVersion {
 #[derive(Deserialize)]
 struct VersionJson {
     downloads: VersionJsonDownloads,
+    #[serde(rename = "javaVersion")]
+    java_version: Option<VersionJsonJava>,
+}
+/// 版本服务端的JSON-javaVersion字段
+#[derive(Deserialize)]
+struct VersionJsonJava {
+    #[serde(rename = "majorVersion")]
+    major_version: usize,
 }
 /// 版本服务端的JSON-downloads字段
 #[derive(Deserialize)]
@@ -129,6 +219,18 @@ impl ManifestVersion {
             server_download.downloads.server.sha1,
         ))
     }
+
+    /// 获取运行该版本需要的 Java 版本，旧版本没有记录时为 8
+    pub fn java_version(&self) -> Result<usize, Error> {
+        let response = reqwest::blocking::get(&self.url)?;
+        if !response.status().is_success() {
+            return Err(Error::msg(format!("Request failed: {}", response.status())));
+        }
+        Ok(response
+            .json::<VersionJson>()?
+            .java_version
+            .map_or(8, |x| x.major_version))
+    }
 }
 
 // 核心逻辑函数
@@ -155,8 +257,8 @@ impl VersionInfo {
         version_name: &str,
         initial_server_type: ServerType,
     ) -> Result<Self, Error> {
-        // Other 类型直接返回
-        if initial_server_type == ServerType::Other {
+        // Other 类型和代理服务端直接返回
+        if initial_server_type == ServerType::Other || initial_server_type.is_proxy() {
             // 对 Other 类型尝试猜测版本类型
             let version_type = VersionInfo::guess_version_type(version_name);
             return Ok(Self::new(
@@ -0,0 +1,126 @@
+use crate::project_manager::Config;
+use crate::project_manager::create::get_input;
+use crate::project_manager::lock::{Lock, LockedPlugin};
+use crate::project_manager::run::backup_before_update;
+use crate::project_manager::tools::{
+    PluginArtifact, install_plugin, plugin_is_current, plugin_platform, remove_plugin,
+    resolve_plugin,
+};
+use anyhow::Error;
+use colored::Colorize;
+use tracing::{info, warn};
+
+/// 插件更新计划中的操作
+enum Action {
+    /// 安装新插件
+    Install(PluginArtifact),
+    /// 替换已安装的插件
+    Upgrade(LockedPlugin, PluginArtifact),
+    /// 移除已从配置中删除的插件
+    Remove(LockedPlugin),
+}
+
+/// 按照配置文件中的插件列表更新插件
+pub fn update_plugins(config: &Config, yes: bool) -> Result<(), Error> {
+    if !config.plugin_manage.manage {
+        return Err(Error::msg(
+            "Plugin management is disabled in the configuration",
+        ));
+    }
+    if plugin_platform(&config.project.server_type).is_none() {
+        return Err(Error::msg(format!(
+            "The server type {:?} does not support plugins",
+            config.project.server_type
+        )));
+    }
+
+    // 读取锁文件中已安装的插件
+    let mut lock = Lock::load()?;
+
+    // 解析插件版本，生成更新计划
+    let mut actions = vec![];
+    for plugin in &config.plugin {
+        let artifact =
+            resolve_plugin(plugin, &config.project.server_type, &config.project.version)?;
+        match lock.plugin(&plugin.name) {
+            Some(installed)
+                if plugin_is_current(installed, &artifact, plugin.checksum.as_deref()) => {}
+            Some(installed) => actions.push(Action::Upgrade(installed.clone(), artifact)),
+            None => actions.push(Action::Install(artifact)),
+        }
+    }
+    for installed in &lock.plugin {
+        if !config.plugin.iter().any(|x| x.name == installed.name) {
+            actions.push(Action::Remove(installed.clone()));
+        }
+    }
+
+    if actions.is_empty() {
+        info!("All plugins are up to date");
+        return Ok(());
+    }
+
+    // 打印更新计划
+    println!("The following changes will be made:");
+    for action in &actions {
+        match action {
+            Action::Install(new) => {
+                println!("  {} {} {}", "+".green(), new.name, new.version.green())
+            }
+            Action::Upgrade(old, new) => println!(
+                "  {} {} {} -> {}",
+                "~".yellow(),
+                new.name,
+                old.version.yellow(),
+                new.version.green()
+            ),
+            Action::Remove(old) => println!("  {} {} {}", "-".red(), old.name, old.version.red()),
+        }
+    }
+
+    // 确认更新
+    if !yes {
+        println!("Do you want to continue? [y/N]");
+        if !matches!(get_input().trim().to_lowercase().as_str(), "y" | "yes") {
+            warn!("The update has been cancelled");
+            return Ok(());
+        }
+    }
+
+    // 更新前备份
+    backup_before_update(config)?;
+
+    // 执行更新，每一步完成后都写入锁文件
+    for action in actions {
+        match action {
+            Action::Install(new) => {
+                let installed = install_plugin(&new, checksum_of(config, &new.name))?;
+                lock.upsert_plugin(installed);
+            }
+            Action::Upgrade(old, new) => {
+                let installed = install_plugin(&new, checksum_of(config, &new.name))?;
+                if old.file != installed.file {
+                    remove_plugin(&old)?;
+                }
+                lock.upsert_plugin(installed);
+            }
+            Action::Remove(old) => {
+                remove_plugin(&old)?;
+                lock.plugin.retain(|x| x.name != old.name);
+            }
+        }
+        lock.save()?;
+    }
+
+    info!("{}", "The plugins have been successfully updated".green());
+    Ok(())
+}
+
+/// 获取配置文件中插件的校验值
+fn checksum_of<'a>(config: &'a Config, name: &str) -> Option<&'a str> {
+    config
+        .plugin
+        .iter()
+        .find(|x| x.name == name)
+        .and_then(|x| x.checksum.as_deref())
+}
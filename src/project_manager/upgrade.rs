@@ -0,0 +1,195 @@
+use crate::project_manager::create::get_input;
+use crate::project_manager::lock::{Lock, LockedCore};
+use crate::project_manager::run::backup_before_update;
+use crate::project_manager::tools::{LaunchSpec, ServerType, extract_bds, hash_file, provider_for};
+use crate::project_manager::{BDS_ARCHIVE, CONFIG_FILE, Config, LOCK_FILE, ROLLBACK_DIR, pre_run};
+use anyhow::Error;
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+/// 升级服务端核心，`to` 为目标游戏版本，缺省时升级到当前版本的最新构建
+pub fn upgrade_server(config: Config, to: Option<String>, yes: bool) -> Result<(), Error> {
+    match config.project.server_type {
+        ServerType::Other if config.provider.is_none() => {
+            return Err(Error::msg(
+                "Other servers need a [provider] section to support the upgrade function",
+            ));
+        }
+        _ => (),
+    }
+
+    // 确定目标版本
+    let provider = provider_for(&config);
+    let target = match to.as_deref() {
+        None => config.project.version.clone(),
+        Some("latest") => provider.latest_version(config.project.channel.as_ref())?,
+        Some(v) => v.to_string(),
+    };
+    let version_info = provider.version_info(&target)?;
+    // 固定的构建号只对当前版本有效，升级到其他版本时忽略并在升级后清除
+    let build = if target == config.project.version {
+        config.project.build
+    } else {
+        if let Some(build) = config.project.build {
+            warn!(
+                "The pinned build {} only applies to {}, use the latest build of {}",
+                build, config.project.version, target
+            );
+        }
+        None
+    };
+    let artifact = provider.resolve(
+        &version_info,
+        build,
+        config.project.loader_version.as_deref(),
+        config.project.channel.as_ref(),
+    )?;
+
+    // 与当前文件对比，来源没有提供哈希值时与锁文件对比，
+    // 直接运行的服务端记录的是压缩包的哈希值，只对比下载链接
+    let execute = config.project.execute.clone();
+    let native = provider.launch() == LaunchSpec::Native;
+    if execute.is_file() && target == config.project.version {
+        let (sha256, sha1) = hash_file(&execute)?;
+        let locked = Lock::load()?.core_for(&config).is_some_and(|x| {
+            x.url.as_ref() == Some(&artifact.url)
+                && x.loader == artifact.loader
+                && (native || x.sha256 == sha256)
+        });
+        if locked
+            || artifact.sha256.as_ref().is_some_and(|x| x == &sha256)
+            || artifact.sha1.as_ref().is_some_and(|x| x == &sha1)
+        {
+            info!("The server is already up to date");
+            return Ok(());
+        }
+    }
+
+    // 打印升级计划
+    println!(
+        "{:?} {} -> {}{}{}",
+        artifact.server_type,
+        config.project.version.yellow(),
+        artifact.version.green(),
+        artifact
+            .build
+            .map(|x| format!(" (build {})", x))
+            .unwrap_or_default(),
+        artifact
+            .loader
+            .as_ref()
+            .map(|x| format!(" (loader {})", x))
+            .unwrap_or_default()
+    );
+    // 打印构建包含的提交
+    for commit in &artifact.changelog {
+        println!("  {} {}", "*".bright_black(), commit);
+    }
+    if !yes {
+        println!("Do you want to continue? [y/N]");
+        if !matches!(get_input().trim().to_lowercase().as_str(), "y" | "yes") {
+            warn!("The upgrade has been cancelled");
+            return Ok(());
+        }
+    }
+
+    // 升级前备份
+    backup_before_update(&config)?;
+
+    // 保留当前的服务端和配置，用于回滚
+    if Path::new(ROLLBACK_DIR).exists() {
+        fs::remove_dir_all(ROLLBACK_DIR)?;
+    }
+    fs::create_dir_all(ROLLBACK_DIR)?;
+    fs::copy(CONFIG_FILE, Path::new(ROLLBACK_DIR).join(CONFIG_FILE))?;
+    if Path::new(LOCK_FILE).is_file() {
+        fs::copy(LOCK_FILE, Path::new(ROLLBACK_DIR).join(LOCK_FILE))?;
+    }
+    if execute.is_file() {
+        fs::copy(
+            &execute,
+            Path::new(ROLLBACK_DIR).join(rollback_name(&execute)?),
+        )?;
+    }
+    // 基岩版的服务端包含多个文件，保留安装时的压缩包用于恢复
+    if native && Path::new(BDS_ARCHIVE).is_file() {
+        fs::copy(BDS_ARCHIVE, rollback_archive()?)?;
+    }
+
+    // 替换服务端
+    let file = provider.install(&artifact, &execute)?;
+    let mut lock = Lock::load()?;
+    lock.core = Some(LockedCore::new(&artifact, &file));
+    lock.save()?;
+    let mut new_config = config;
+    new_config.project.build = build;
+    new_config.project.version = version_info.name;
+    new_config.project.version_type = version_info.version_type;
+
+    // 检查新的服务端并准备运行环境，失败则回滚
+    let checked = if provider.check(&execute) {
+        pre_run(&new_config)
+    } else {
+        Err(Error::msg("The new server cannot be used"))
+    };
+    if let Err(e) = checked {
+        error!("The upgrade failed: {}", e);
+        rollback()?;
+        return Err(e);
+    }
+    new_config.to_file(CONFIG_FILE)?;
+
+    info!(
+        "{}",
+        "The server has been successfully upgraded, run `pacmine upgrade --rollback` to undo it"
+            .green()
+    );
+    Ok(())
+}
+
+/// 回滚到上一次升级前的服务端和配置
+pub fn rollback() -> Result<(), Error> {
+    let saved_config = Path::new(ROLLBACK_DIR).join(CONFIG_FILE);
+    if !saved_config.is_file() {
+        return Err(Error::msg("There is no upgrade that can be rolled back"));
+    }
+    let config = Config::from_file(&saved_config)?;
+    let execute = config.project.execute.clone();
+    let saved_execute = Path::new(ROLLBACK_DIR).join(rollback_name(&execute)?);
+    let saved_archive = rollback_archive()?;
+    if saved_archive.is_file() {
+        // 重新解压升级前的压缩包，存档和配置不受影响
+        extract_bds(&saved_archive, &execute)?;
+        fs::copy(&saved_archive, BDS_ARCHIVE)?;
+    } else if saved_execute.is_file() {
+        fs::copy(&saved_execute, &execute)?;
+    }
+    fs::copy(&saved_config, CONFIG_FILE)?;
+    let saved_lock = Path::new(ROLLBACK_DIR).join(LOCK_FILE);
+    if saved_lock.is_file() {
+        fs::copy(&saved_lock, LOCK_FILE)?;
+    }
+    fs::remove_dir_all(ROLLBACK_DIR)?;
+    info!(
+        "Rolled back to {:?} {}",
+        config.project.server_type, config.project.version
+    );
+    Ok(())
+}
+
+/// 回滚目录中保存服务端使用的文件名
+fn rollback_name(execute: &Path) -> Result<&std::ffi::OsStr, Error> {
+    execute
+        .file_name()
+        .ok_or(Error::msg("The executable path is invalid"))
+}
+
+/// 回滚目录中保存的基岩版压缩包
+fn rollback_archive() -> Result<PathBuf, Error> {
+    let name = Path::new(BDS_ARCHIVE)
+        .file_name()
+        .ok_or(Error::msg("The archive path is invalid"))?;
+    Ok(Path::new(ROLLBACK_DIR).join(name))
+}